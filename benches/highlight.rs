@@ -0,0 +1,51 @@
+//! ファイルを開く際のハイライトにかかる時間を、毎回シンタックス定義とテーマを
+//! 読み込む場合(以前の実装)と読み込み済みのものを使い回す場合で比較する
+//!
+//! `cargo bench --bench highlight`
+
+use std::time::{Duration, Instant};
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+/// 計測する回数
+const RUNS: u32 = 20;
+/// 計測に使うテーマ
+const THEME: &str = "base16-eighties.dark";
+
+/// `text`をRustとしてハイライトする
+fn highlight(text: &str, ps: &SyntaxSet, ts: &ThemeSet) {
+    let syntax = ps.find_syntax_by_extension("rs").unwrap();
+    let mut h = HighlightLines::new(syntax, &ts.themes[THEME]);
+    for line in text.lines() {
+        h.highlight_line(line, ps).unwrap();
+    }
+}
+
+/// `f`を[RUNS]回実行した一回あたりの時間
+fn measure(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let text = include_str!("../src/main.rs");
+
+    let uncached = measure(|| {
+        let ps = SyntaxSet::load_defaults_nonewlines();
+        let ts = ThemeSet::load_defaults();
+        highlight(text, &ps, &ts);
+    });
+
+    // 初回の読み込みは計測に含めない
+    let ps = SyntaxSet::load_defaults_nonewlines();
+    let ts = ThemeSet::load_defaults();
+    let cached = measure(|| highlight(text, &ps, &ts));
+
+    println!("毎回読み込む場合: {uncached:?}/回");
+    println!("使い回す場合    : {cached:?}/回");
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use crate::{
+    config::{self, Config},
+    ui::syntax,
+};
+
+/// ヘルプ
+pub const HELP: &str = "\
+tui-cat: ターミナル上でファイルの中身を確認するアプリケーション
+
+使い方:
+    tui-cat [オプション] [パス]
+
+引数:
+    [パス]  開くフォルダ・ファイル (省略時はカレントディレクトリ)
+            ファイルを指定した場合はそのファイルを開いた状態で起動する
+
+オプション:
+    -s, --space <N>      タブを何スペースに展開するか (0~16)
+    -t, --theme <名前>   シンタックスハイライトのテーマ
+    -n, --line-numbers   ファイルを開いた時に行数を表示する
+    -a, --hidden         隠しファイルを表示する (設定ファイルで非表示にしている場合)
+    -H, --no-hidden      隠しファイルを表示しない
+    -i, --ignore         .gitignore・.ignoreで無視されるものを一覧から除く
+    -h, --help           このヘルプを表示する
+    -V, --version        バージョンを表示する";
+
+/// コマンドライン引数の解析結果
+#[derive(Debug)]
+pub enum Command {
+    /// 起動する。開くパスが指定されていれば[Some]
+    Run(Option<PathBuf>),
+    /// ヘルプを表示する
+    Help,
+    /// バージョンを表示する
+    Version,
+}
+
+/// バージョンの文字列
+#[inline]
+pub fn version() -> String {
+    format!("tui-cat {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// コマンドライン引数(プログラム名を除く)を解析し、指定された値で`config`を上書きする
+pub fn parse<I>(args: I, config: &mut Config) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    let mut path: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-s" | "--space" => {
+                let value = value(&arg, args.next())?;
+                config.tab_width = config::parse_number(&arg, &value, config::TAB_WIDTH_RANGE)?;
+            }
+            "-t" | "--theme" => {
+                let value = value(&arg, args.next())?;
+                if !syntax::theme_exists(&value) {
+                    return Err(format!("テーマ\"{value}\"が見つかりません"));
+                }
+                config.theme = value;
+            }
+            "-n" | "--line-numbers" => config.line_numbers = true,
+            "-a" | "--hidden" => config.show_hidden = true,
+            "-H" | "--no-hidden" => config.show_hidden = false,
+            "-i" | "--ignore" => config.respect_ignore = true,
+            _ if arg.starts_with('-') => {
+                return Err(format!("不明なオプション {arg}"));
+            }
+            _ => {
+                if path.is_some() {
+                    return Err(format!("パスは一つだけ指定してください: {arg}"));
+                }
+
+                let p = PathBuf::from(&arg);
+                path = Some(
+                    p.canonicalize()
+                        .map_err(|err| format!("{arg}を開けません: {err}"))?,
+                );
+            }
+        }
+    }
+
+    Ok(Command::Run(path))
+}
+
+/// オプションの値を取得する。無ければエラー
+fn value(arg: &str, next: Option<String>) -> Result<String, String> {
+    next.ok_or(format!("{arg}には値を指定してください"))
+}
+
+#[test]
+fn parse_test() {
+    let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    let mut config = Config::default();
+
+    let command = parse(args(&["-s", "2", "-n", "src"]), &mut config).unwrap();
+    assert!(matches!(command, Command::Run(Some(p)) if p.ends_with("src")));
+    assert_eq!(config.tab_width, 2);
+    assert!(config.line_numbers);
+
+    assert!(matches!(
+        parse(args(&["--help"]), &mut config),
+        Ok(Command::Help)
+    ));
+    assert!(parse(args(&["--space", "x"]), &mut config).is_err());
+    assert!(parse(args(&["--space", "17"]), &mut config).is_err());
+    parse(args(&["-H"]), &mut config).unwrap();
+    assert!(!config.show_hidden);
+    assert!(parse(args(&["--space"]), &mut config).is_err());
+    assert!(parse(args(&["--theme", "none"]), &mut config).is_err());
+    assert!(parse(args(&["--foo"]), &mut config).is_err());
+    assert!(parse(args(&["no_such_path"]), &mut config).is_err());
+}
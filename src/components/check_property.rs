@@ -1,12 +1,16 @@
+use crate::components::symlink::Symlink;
+
 use chrono::{DateTime, Datelike, NaiveDateTime, TimeDelta, Timelike, Utc};
 use ratatui::{
     style::Style,
     text::{Line, Span, Text},
 };
 use std::{
-    fs::Metadata,
-    io::{Error, ErrorKind},
+    collections::HashMap,
+    fs::{self, Metadata},
+    io::Error,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
 /// 時差
@@ -28,6 +32,16 @@ pub struct Property {
     size: u64,
     /// [true]アクセス可能 [false]アクセス不可
     user_access: bool,
+    /// フォルダかどうか
+    is_dir: bool,
+    /// Unixの権限(種類のビットを含む)。Unix以外は[None]
+    mode: Option<u32>,
+    /// 所有者のユーザーID。Unix以外は[None]
+    uid: Option<u32>,
+    /// シンボリックリンクの場合はリンク先
+    link: Option<Symlink>,
+    /// ファイルの文字コード
+    encoding: Option<&'static str>,
 }
 
 enum Get {
@@ -41,32 +55,78 @@ enum Get {
 
 impl Property {
     pub fn new(path: &Path) -> Option<Property> {
-        let data = match path.metadata() {
+        // リンク切れの場合はリンク自体の情報を使う
+        let data = match path.metadata().or_else(|_| path.symlink_metadata()) {
             Ok(x) => x,
             Err(_) => return None,
         };
 
         Some(Property {
-            file_name: path.iter().last()?.to_str()?.to_string(),
+            file_name: path.iter().next_back()?.to_str()?.to_string(),
             place: path.to_path_buf(),
             create_time: get_time(&data, Get::CreateTime),
             last_update: get_time(&data, Get::UpdateTime),
             last_access: get_time(&data, Get::LastAccess),
             size: data.len(),
             user_access: data.accessed().is_ok(),
+            is_dir: data.is_dir(),
+            mode: mode(&data),
+            uid: uid(&data),
+            link: Symlink::read(path),
+            encoding: None,
         })
     }
 
+    /// 判定したファイルの文字コードを設定する
+    pub fn set_encoding(&mut self, encoding: &'static str) {
+        self.encoding = Some(encoding);
+    }
+
+    /// 一覧に表示する大きさ 例: "1.5K"。フォルダは"-"
+    pub fn human_size(&self) -> String {
+        if self.is_dir {
+            return "-".to_string();
+        }
+        human_size(self.size)
+    }
+
+    /// 一覧に表示する最終更新日時 例: "3日前"
+    pub fn relative_update(&self) -> String {
+        match &self.last_update {
+            Ok(time) => {
+                let now = Utc::now().naive_utc() + TimeDelta::hours(TIME_DIFFERENSE);
+                relative_time(now.signed_duration_since(*time))
+            }
+            Err(_) => "-".to_string(),
+        }
+    }
+
+    /// `ls -l`と同じ形式の権限 例: "drwxr-xr-x"
+    pub fn permissions(&self) -> String {
+        match self.mode {
+            Some(mode) => permission_string(mode),
+            None => if self.user_access { "可能" } else { "不可" }.to_string(),
+        }
+    }
+
+    /// 所有者のユーザー名。分からない場合はユーザーID
+    pub fn owner(&self) -> String {
+        match self.uid {
+            Some(uid) => user_name(uid),
+            None => "-".to_string(),
+        }
+    }
+
     pub fn to_text(&self) -> Text<'_> {
         let access_check = |f: bool| -> &str {
-            if let true = f {
+            if f {
                 "可能"
             } else {
                 "不可"
             }
         };
 
-        let spans = vec![
+        let mut spans = vec![
             Line::from(Span::styled(
                 "プロパティモード",
                 Style::default().fg(ratatui::style::Color::DarkGray),
@@ -76,10 +136,23 @@ impl Property {
             Line::from("場所：".to_string() + self.place.to_str().unwrap()),
             Line::from("サイズ：".to_string() + &self.size.to_string()),
             Line::from("アクセス：".to_string() + access_check(self.user_access)),
+            Line::from("権限：".to_string() + &self.permissions()),
+            Line::from("所有者：".to_string() + &self.owner()),
+        ];
+
+        if let Some(link) = &self.link {
+            spans.push(Line::from("リンク：".to_string() + &link.label()));
+        }
+
+        spans.extend([
             Line::from("ファイル作成日：".to_string() + &time_check(&self.create_time)),
             Line::from("最終更新日　　：".to_string() + &time_check(&self.last_update)),
             Line::from("最終アクセス　：".to_string() + &time_check(&self.last_access)),
-        ];
+        ]);
+
+        if let Some(encoding) = self.encoding {
+            spans.push(Line::from("文字コード　　：".to_string() + encoding));
+        }
 
         Text::from(spans)
     }
@@ -102,7 +175,7 @@ fn get_time(metadata: &Metadata, x: Get) -> Result<NaiveDateTime, Error> {
     {
         Ok(x)
     } else {
-        Err(Error::new(ErrorKind::Other, "err"))
+        Err(Error::other("err"))
     }
 }
 
@@ -126,3 +199,110 @@ fn time_check(time: &Result<NaiveDateTime, Error>) -> String {
         Err(_) => "取得できませんでした".to_string(),
     }
 }
+
+/// バイト数を1024ごとの単位で表す 例: 1536 -> "1.5K"
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if size < 1024 {
+        return format!("{size}B");
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// 経過時間を"3日前"のように表す
+fn relative_time(elapsed: TimeDelta) -> String {
+    let minutes = elapsed.num_minutes();
+    let hours = elapsed.num_hours();
+    let days = elapsed.num_days();
+    match () {
+        _ if minutes < 1 => "今".to_string(),
+        _ if hours < 1 => format!("{minutes}分前"),
+        _ if days < 1 => format!("{hours}時間前"),
+        _ if days < 30 => format!("{days}日前"),
+        _ if days < 365 => format!("{}か月前", days / 30),
+        _ => format!("{}年前", days / 365),
+    }
+}
+
+/// 権限のビットを"drwxr-xr-x"の形にする
+fn permission_string(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        _ => '-',
+    };
+    let bits = ['r', 'w', 'x'];
+    std::iter::once(kind)
+        .chain((0..9).map(|i| {
+            if mode & (1 << (8 - i)) != 0 {
+                bits[i % 3]
+            } else {
+                '-'
+            }
+        }))
+        .collect()
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn mode(_: &Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn uid(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn uid(_: &Metadata) -> Option<u32> {
+    None
+}
+
+/// `/etc/passwd`からユーザーIDに対応する名前を探す。見つからなければユーザーID
+fn user_name(uid: u32) -> String {
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+
+    let users = USERS.get_or_init(|| {
+        fs::read_to_string("/etc/passwd")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let uid = fields.nth(1)?.parse().ok()?;
+                Some((uid, name.to_string()))
+            })
+            .collect()
+    });
+    users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+}
+
+#[test]
+fn format_test() {
+    assert_eq!(human_size(512), "512B");
+    assert_eq!(human_size(1536), "1.5K");
+    assert_eq!(human_size(20 * 1024 * 1024), "20M");
+    assert_eq!(relative_time(TimeDelta::seconds(30)), "今");
+    assert_eq!(relative_time(TimeDelta::hours(5)), "5時間前");
+    assert_eq!(relative_time(TimeDelta::days(400)), "1年前");
+    assert_eq!(permission_string(0o040755), "drwxr-xr-x");
+    assert_eq!(permission_string(0o100644), "-rw-r--r--");
+}
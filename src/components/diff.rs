@@ -0,0 +1,274 @@
+use crate::components::wrap::wrap;
+use crate::ui::syntax;
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span, Text},
+};
+use similar::{DiffTag, TextDiff};
+use std::{ops::Range, path::PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+/// 変更箇所の前後に表示する変わっていない行の数
+const CONTEXT_LINES: usize = 3;
+
+/// 削除された行の背景色
+const REMOVED: Color = Color::Rgb(80, 20, 20);
+/// 追加された行の背景色
+const ADDED: Color = Color::Rgb(20, 70, 20);
+/// 左右で書き換わった行の背景色
+const CHANGED: Color = Color::Rgb(70, 60, 10);
+
+/// 差分の表示方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMode {
+    /// 一列にまとめて表示する
+    Unified,
+    /// 左右に並べて表示する
+    SideBySide,
+}
+
+/// 印を付けたファイルとの差分
+#[derive(Debug)]
+pub struct Diff {
+    /// 比較元のファイル
+    pub old: PathBuf,
+    pub mode: DiffMode,
+    /// 表示した際の幅
+    pub width: usize,
+    /// 各変更箇所の見出しの行
+    pub hunks: Vec<usize>,
+    /// 追加された行数
+    pub added: usize,
+    /// 削除された行数
+    pub removed: usize,
+}
+
+/// 差分の一行分の片側。行番号と装飾済みの行
+type Side<'a> = Option<(usize, Line<'a>)>;
+
+impl Diff {
+    pub fn new(old: PathBuf, mode: DiffMode) -> Diff {
+        Diff {
+            old,
+            mode,
+            width: 0,
+            hunks: Vec::new(),
+            added: 0,
+            removed: 0,
+        }
+    }
+
+    /// `old`と`new`の差分を`width`の幅で表示する。<br>
+    /// 両方を`syntax`でハイライトし、変更された行は背景色を付ける。末尾の改行の有無は無視する
+    pub fn render(
+        &mut self,
+        old: &str,
+        new: &str,
+        width: usize,
+        syntax: Option<&str>,
+        tab_width: usize,
+        theme: &str,
+    ) -> Text<'static> {
+        let old = with_newline(old);
+        let new = with_newline(new);
+        let diff = TextDiff::from_lines(old.as_str(), new.as_str());
+
+        let highlight = |text: &str| syntax::hylight(text, syntax, tab_width, theme).lines;
+        let old_lines = highlight(&old);
+        let new_lines = highlight(&new);
+        let line =
+            |lines: &[Line<'static>], i: usize| (i + 1, lines.get(i).cloned().unwrap_or_default());
+
+        // 行番号の桁数
+        let digits = (old_lines.len().max(new_lines.len()).max(1).ilog10() + 1) as usize;
+
+        self.width = width;
+        self.hunks.clear();
+        self.added = 0;
+        self.removed = 0;
+
+        let mut rows: Vec<Line<'static>> = Vec::new();
+        for group in diff.grouped_ops(CONTEXT_LINES) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            self.hunks.push(rows.len());
+            rows.push(Line::styled(
+                format!(
+                    "@@ -{} +{} @@",
+                    hunk_range(&old_range),
+                    hunk_range(&new_range)
+                ),
+                Style::default().fg(Color::Cyan),
+            ));
+
+            for op in group {
+                let (tag, old_range, new_range) = op.as_tag_tuple();
+                let olds = old_range.map(|i| line(&old_lines, i)).collect::<Vec<_>>();
+                let news = new_range.map(|i| line(&new_lines, i)).collect::<Vec<_>>();
+
+                match tag {
+                    DiffTag::Equal => {
+                        for (old, new) in olds.into_iter().zip(news) {
+                            rows.extend(self.row(Some(old), Some(new), tag, digits));
+                        }
+                    }
+                    DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                        self.removed += olds.len();
+                        self.added += news.len();
+
+                        if self.mode == DiffMode::Unified || tag != DiffTag::Replace {
+                            for old in olds {
+                                rows.extend(self.row(Some(old), None, DiffTag::Delete, digits));
+                            }
+                            for new in news {
+                                rows.extend(self.row(None, Some(new), DiffTag::Insert, digits));
+                            }
+                        } else {
+                            // 書き換わった行は左右に並べる
+                            let count = olds.len().max(news.len());
+                            let mut olds = olds.into_iter();
+                            let mut news = news.into_iter();
+                            for _ in 0..count {
+                                rows.extend(self.row(olds.next(), news.next(), tag, digits));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if rows.is_empty() {
+            return Text::from("差分はありません");
+        }
+        Text::from(rows)
+    }
+
+    /// 差分の一行。統合表示で両方ある場合は変わっていない行
+    fn row(
+        &self,
+        old: Side<'static>,
+        new: Side<'static>,
+        tag: DiffTag,
+        digits: usize,
+    ) -> Option<Line<'static>> {
+        let number = |side: &Side| match side {
+            Some((n, _)) => Span::styled(
+                format!("{n:>digits$} "),
+                Style::default().fg(Color::DarkGray),
+            ),
+            None => Span::raw(" ".repeat(digits + 1)),
+        };
+
+        match self.mode {
+            DiffMode::Unified => {
+                let (sign, bg, content) = match (tag, &old, &new) {
+                    (DiffTag::Delete, Some((_, line)), _) => ("-", Some(REMOVED), line.clone()),
+                    (DiffTag::Insert, _, Some((_, line))) => ("+", Some(ADDED), line.clone()),
+                    (_, _, Some((_, line))) => (" ", None, line.clone()),
+                    _ => return None,
+                };
+                let mut spans = vec![
+                    number(&old),
+                    number(&new),
+                    Span::raw(sign.to_string() + " "),
+                ];
+                spans.extend(content.spans);
+                let mut line = Line::from(spans);
+                if let Some(bg) = bg {
+                    line = line.style(Style::default().bg(bg));
+                }
+                Some(line)
+            }
+            DiffMode::SideBySide => {
+                let (old_bg, new_bg) = match tag {
+                    DiffTag::Equal => (None, None),
+                    DiffTag::Replace => (Some(CHANGED), Some(CHANGED)),
+                    _ => (Some(REMOVED), Some(ADDED)),
+                };
+                // 区切りの" │ "を除いた半分の幅
+                let half = self.width.saturating_sub(3) / 2;
+                let content = half.saturating_sub(digits + 1);
+
+                let mut spans = vec![number(&old)];
+                spans.extend(fit(old.map(|(_, line)| line), content, old_bg));
+                spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+                spans.push(number(&new));
+                spans.extend(fit(new.map(|(_, line)| line), content, new_bg));
+                Some(Line::from(spans))
+            }
+        }
+    }
+}
+
+/// 末尾に改行がなければ付ける
+fn with_newline(text: &str) -> String {
+    let mut text = text.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// 見出しに表示する`開始行,行数`
+fn hunk_range(range: &Range<usize>) -> String {
+    format!("{},{}", range.start + 1, range.len())
+}
+
+/// `line`を`width`の幅に切り詰め、足りない分は空白で埋める。行があって`bg`があれば背景色を付ける
+fn fit(line: Option<Line<'static>>, width: usize, bg: Option<Color>) -> Vec<Span<'static>> {
+    let bg = bg.filter(|_| line.is_some());
+    let spans = line.map(|line| line.spans).unwrap_or_default();
+    let mut spans = wrap(spans, width, false)
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let used: usize = spans.iter().map(|span| span.content.width()).sum();
+    spans.push(Span::raw(" ".repeat(width.saturating_sub(used))));
+
+    match bg {
+        Some(bg) => spans
+            .into_iter()
+            .map(|span| span.patch_style(Style::default().bg(bg)))
+            .collect(),
+        None => spans,
+    }
+}
+
+#[test]
+fn diff_test() {
+    let plain = |text: &Text| -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    };
+
+    let old = "a\nb\nc\n";
+    let new = "a\nB\nc\nd";
+    let mut diff = Diff::new(PathBuf::from("old"), DiffMode::Unified);
+    let text = diff.render(old, new, 40, None, 4, "");
+    assert_eq!(
+        plain(&text),
+        vec![
+            "@@ -1,3 +1,4 @@",
+            "1 1   a",
+            "2   - b",
+            "  2 + B",
+            "3 3   c",
+            "  4 + d",
+        ]
+    );
+    assert_eq!((diff.added, diff.removed), (2, 1));
+    assert_eq!(diff.hunks, vec![0]);
+
+    let mut diff = Diff::new(PathBuf::from("old"), DiffMode::SideBySide);
+    let text = diff.render(old, new, 13, None, 4, "");
+    assert_eq!(plain(&text)[2], "2 b   │ 2 B  ");
+
+    let text = diff.render(old, old, 13, None, 4, "");
+    assert_eq!(plain(&text), vec!["差分はありません"]);
+}
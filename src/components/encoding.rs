@@ -0,0 +1,90 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::{fs::File, io::Read, path::Path};
+
+/// 文字コードの判定に使う先頭のバイト数
+const SNIFF_SIZE: usize = 64 * 1024;
+
+/// ファイルの先頭[SNIFF_SIZE]バイトを読み込む
+pub fn sniff(path: &Path) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(SNIFF_SIZE);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(SNIFF_SIZE as u64).read_to_end(&mut buf);
+    }
+    buf
+}
+
+/// 文字コードを判定する。<br>
+/// BOM → UTF-16のNULの並び → UTF-8として読めるか → 内容からの推測 の順に調べる
+pub fn detect(buf: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(buf) {
+        return encoding;
+    }
+    if let Some(encoding) = detect_utf16(buf) {
+        return encoding;
+    }
+
+    match std::str::from_utf8(buf) {
+        Ok(_) => return UTF_8,
+        // 末尾で文字が途中で切れている場合はUTF-8とみなす
+        Err(err) if err.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(buf, buf.len() < SNIFF_SIZE);
+    detector.guess(None, true)
+}
+
+/// BOMなしのUTF-16を判定する。<br>
+/// ASCIIの文字が多ければ、上位バイトのNULが偶数・奇数どちらかに偏る
+fn detect_utf16(buf: &[u8]) -> Option<&'static Encoding> {
+    let pairs = buf.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+
+    let even = buf.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd = buf.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    if odd * 10 >= pairs * 7 && even * 10 <= pairs {
+        Some(UTF_16LE)
+    } else if even * 10 >= pairs * 7 && odd * 10 <= pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// 指定した文字コードで文字列に変換する。BOMは取り除く
+pub fn decode(buf: &[u8], encoding: &'static Encoding) -> String {
+    encoding.decode_with_bom_removal(buf).0.into_owned()
+}
+
+/// "shift_jis"や"euc-jp"などの名前から文字コードを取得する
+pub fn find(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// UTF-16かどうか
+#[inline]
+pub fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+#[test]
+fn detect_test() {
+    let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、世界。日本語のテキストです。");
+    assert_eq!(detect(&sjis).name(), "Shift_JIS");
+    assert_eq!(
+        decode(&sjis, detect(&sjis)),
+        "こんにちは、世界。日本語のテキストです。"
+    );
+
+    let utf16: Vec<u8> = "hello".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    assert_eq!(detect(&utf16), UTF_16LE);
+    assert_eq!(decode(&utf16, UTF_16LE), "hello");
+
+    assert_eq!(detect("テキスト".as_bytes()), UTF_8);
+    assert_eq!(find("euc-jp").map(Encoding::name), Some("EUC-JP"));
+}
@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+
+/// 連続して一致した場合の加点
+const CONSECUTIVE_BONUS: i64 = 15;
+/// 単語の先頭で一致した場合の加点
+const WORD_START_BONUS: i64 = 10;
+/// 名前の先頭で一致した場合の加点
+const PREFIX_BONUS: i64 = 20;
+/// 一致しなかった文字一つ当たりの減点
+const GAP_PENALTY: i64 = 1;
+
+/// ファイル一覧の絞り込み状態
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// 絞り込み文字列
+    pub query: String,
+    /// 絞り込み文字列の入力中かどうか
+    pub input_mode: bool,
+    /// 絞り込み前の一覧 \[フォルダ, ファイル\]の順
+    pub source: [Vec<OsString>; 2],
+    /// 絞り込み後の一覧で一致した文字の位置(文字単位)
+    pub positions: [Vec<Vec<usize>>; 2],
+}
+
+impl Filter {
+    /// 絞り込み中かどうか
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    /// 絞り込み状態を初期化する
+    pub fn clear(&mut self) {
+        *self = Filter::default();
+    }
+
+    /// [Filter::source]を[Filter::query]で絞り込み、スコア順に並べて返す
+    pub fn apply(&mut self) -> [Vec<OsString>; 2] {
+        let mut items: [Vec<OsString>; 2] = Default::default();
+
+        for (num, names) in self.source.iter().enumerate() {
+            let mut ranked: Vec<(i64, &OsString, Vec<usize>)> = names
+                .iter()
+                .filter_map(|name| {
+                    let (score, positions) = fuzzy_match(&self.query, &name.to_string_lossy())?;
+                    Some((score, name, positions))
+                })
+                .collect();
+
+            // スコアが同じ場合は元の順番を保つ
+            ranked.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+            self.positions[num] = ranked.iter().map(|(_, _, p)| p.clone()).collect();
+            items[num] = ranked
+                .into_iter()
+                .map(|(_, name, _)| name.clone())
+                .collect();
+        }
+
+        items
+    }
+}
+
+/// `pattern`の文字が`text`に順番通り含まれていればスコアと一致した文字の位置を返す。<br>
+/// 大文字小文字は区別しない
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut start = 0;
+
+    for p in pattern.chars().flat_map(char::to_lowercase) {
+        let found = (start..text.len()).find(|&i| text[i].to_lowercase().eq(p.to_lowercase()))?;
+
+        if found == 0 {
+            score += PREFIX_BONUS;
+        } else if !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+
+        match positions.last() {
+            Some(&last) if last + 1 == found => score += CONSECUTIVE_BONUS,
+            _ => score -= (found - start) as i64 * GAP_PENALTY,
+        }
+
+        positions.push(found);
+        start = found + 1;
+    }
+
+    Some((score, positions))
+}
+
+#[test]
+fn fuzzy_match_test() {
+    assert!(fuzzy_match("abc", "acb").is_none());
+    assert_eq!(fuzzy_match("SD", "search_dir.rs").unwrap().1, vec![0, 7]);
+
+    // 連続して一致するものが上位に来る
+    let mut filter = Filter {
+        query: "dr".to_string(),
+        source: [
+            vec![],
+            vec!["draw.rs".into(), "d_x_r.rs".into(), "main.rs".into()],
+        ],
+        ..Default::default()
+    };
+    let items = filter.apply();
+    assert_eq!(items[1], vec![OsString::from("draw.rs"), "d_x_r.rs".into()]);
+}
@@ -0,0 +1,461 @@
+use chrono::{DateTime, Local};
+use ratatui::{style::Color, widgets::ListState};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use unicode_width::UnicodeWidthChar;
+
+/// blameに表示する作者名の幅
+const AUTHOR_WIDTH: usize = 12;
+
+/// ファイルのGitでの状態。後のものほど一覧で優先して表示する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    /// .gitignoreで無視されている
+    Ignored,
+    /// 追跡されていない
+    Untracked,
+    /// 追加された
+    Added,
+    /// 変更・削除された
+    Modified,
+    /// マージで衝突している
+    Conflicted,
+}
+
+impl Status {
+    /// 一覧の名前の前に表示する記号
+    pub fn mark(self) -> &'static str {
+        match self {
+            Status::Ignored => "! ",
+            Status::Untracked => "? ",
+            Status::Added => "A ",
+            Status::Modified => "M ",
+            Status::Conflicted => "U ",
+        }
+    }
+
+    /// 一覧に表示する色
+    pub fn color(self) -> Color {
+        match self {
+            Status::Ignored => Color::DarkGray,
+            Status::Untracked => Color::LightRed,
+            Status::Added => Color::LightGreen,
+            Status::Modified => Color::Yellow,
+            Status::Conflicted => Color::Magenta,
+        }
+    }
+
+    /// 変更があるかどうか(無視されているもの以外)
+    #[inline]
+    pub fn is_changed(self) -> bool {
+        self != Status::Ignored
+    }
+}
+
+/// リポジトリ内のファイルのGitでの状態。<br>
+/// 同じリポジトリの中でフォルダを移動する間は読み込み直さずに使い回す
+#[derive(Debug, Default)]
+pub struct GitStatus {
+    /// リポジトリの最上位のフォルダ
+    root: PathBuf,
+    /// リポジトリの最上位から見た一覧のフォルダの位置
+    prefix: PathBuf,
+    /// リポジトリの最上位から見たパスと状態
+    entries: Vec<(PathBuf, Status)>,
+}
+
+impl GitStatus {
+    /// `dir`を含むリポジトリ全体の状態を`git status`で読み込む。<br>
+    /// Gitの作業ツリーでない場合や`git`が無い場合は[None]
+    pub fn load(dir: &Path) -> Option<GitStatus> {
+        let root = find_root(dir)?;
+        let status = git(&root, &["status", "--porcelain=v1", "-z", "--ignored"]).ok()?;
+
+        Some(GitStatus {
+            prefix: dir.strip_prefix(&root).ok()?.to_path_buf(),
+            root,
+            entries: parse(&status),
+        })
+    }
+
+    /// 一覧のフォルダを`dir`に変える。`dir`が同じリポジトリの中に無ければ[false]
+    pub fn change_dir(&mut self, dir: &Path) -> bool {
+        if find_root(dir).as_ref() != Some(&self.root) {
+            return false;
+        }
+        match dir.strip_prefix(&self.root) {
+            Ok(prefix) => {
+                self.prefix = prefix.to_path_buf();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// 一覧のフォルダにある`name`の状態。<br>
+    /// フォルダの場合は中のファイルで一番優先するもの、無視されたフォルダの中なら[Status::Ignored]
+    pub fn get(&self, name: &OsStr) -> Option<Status> {
+        let path = self.prefix.join(name);
+
+        self.entries
+            .iter()
+            .filter(|(entry, status)| {
+                // 本体か、追跡されていない・無視されたフォルダの中か、変更のあるファイルを含むフォルダ
+                path.starts_with(entry) || (entry.starts_with(&path) && status.is_changed())
+            })
+            .map(|(_, status)| *status)
+            .max()
+    }
+}
+
+/// ファイルの履歴の一件
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    /// 作成日 例: 2024-01-31
+    pub date: String,
+    /// コミットメッセージの一行目
+    pub summary: String,
+    /// このコミットでのファイルの場所(リポジトリの最上位から)
+    pub path: String,
+    /// リポジトリの最上位のフォルダ
+    pub root: PathBuf,
+}
+
+impl Commit {
+    /// 短いハッシュ
+    pub fn short(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    /// このコミットでのファイルの中身
+    pub fn content(&self) -> Result<Vec<u8>, String> {
+        let object = format!("{}:{}", self.hash, self.path);
+        git(&self.root, &["show", &object])
+    }
+}
+
+/// 開いているファイルの履歴
+#[derive(Debug, Default)]
+pub struct GitLog {
+    /// 履歴を表示中かどうか
+    pub active: bool,
+    /// 新しい順のコミット
+    pub commits: Vec<Commit>,
+    /// 履歴の選択
+    pub state: ListState,
+}
+
+impl GitLog {
+    /// `file`を変更したコミットを名前の変更も追って読み込む
+    pub fn run(&mut self, file: &Path) -> Result<(), String> {
+        let (dir, name) = split_path(file)?;
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(String::from_utf8_lossy(&root).trim());
+        let output = git(
+            dir,
+            &[
+                "log",
+                "--follow",
+                "--date=short",
+                "--format=%x1e%H%x00%an%x00%ad%x00%s",
+                "--name-only",
+                "--",
+                name,
+            ],
+        )?;
+
+        self.commits = parse_log(&String::from_utf8_lossy(&output), &root);
+        if self.commits.is_empty() {
+            return Err("コミットされていないファイルです".to_string());
+        }
+        self.state.select(Some(0));
+        self.active = true;
+        Ok(())
+    }
+
+    /// 履歴を閉じる
+    pub fn close(&mut self) {
+        self.active = false;
+    }
+
+    /// 選択を一つ次に進める
+    pub fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some((i + 1) % self.commits.len().max(1)));
+        }
+    }
+
+    /// 選択を一つ前に戻す
+    pub fn back(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(
+                i.checked_sub(1)
+                    .unwrap_or(self.commits.len().saturating_sub(1)),
+            ));
+        }
+    }
+
+    /// 選択中のコミット
+    pub fn selected(&self) -> Option<&Commit> {
+        self.commits.get(self.state.selected()?)
+    }
+
+    /// 履歴の表示用文字列 例: "1a2b3c4 2024-01-31 作者名 メッセージ"
+    pub fn format(commit: &Commit) -> String {
+        format!(
+            "{} {} {} {}",
+            commit.short(),
+            commit.date,
+            commit.author,
+            commit.summary
+        )
+    }
+}
+
+/// blameの一行分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blame {
+    pub hash: String,
+    pub author: String,
+    /// 作成日時(UNIX時間)
+    pub time: i64,
+}
+
+impl Blame {
+    /// 行の前に表示する文字列 例: "1a2b3c4 作者名       2024-01-31 │"
+    pub fn label(&self) -> String {
+        // まだコミットしていない行はハッシュが0になる
+        if self.hash.bytes().all(|b| b == b'0') {
+            return format!(
+                "{:<7} {} {:<10} │",
+                "",
+                fit(" 未コミット", AUTHOR_WIDTH),
+                ""
+            );
+        }
+        let date = DateTime::from_timestamp(self.time, 0)
+            .map(|time| time.with_timezone(&Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        format!(
+            "{} {} {date:<10} │",
+            &self.hash[..self.hash.len().min(7)],
+            fit(&self.author, AUTHOR_WIDTH)
+        )
+    }
+}
+
+/// `file`の各行を最後に変更したコミット。`commit`を指定した場合はその時点のもの
+pub fn blame(file: &Path, commit: Option<&Commit>) -> Result<Vec<Blame>, String> {
+    let output = match commit {
+        Some(commit) => git(
+            &commit.root,
+            &[
+                "blame",
+                "--line-porcelain",
+                &commit.hash,
+                "--",
+                &commit.path,
+            ],
+        )?,
+        None => {
+            let (dir, name) = split_path(file)?;
+            git(dir, &["blame", "--line-porcelain", "--", name])?
+        }
+    };
+    Ok(parse_blame(&String::from_utf8_lossy(&output)))
+}
+
+/// `dir`で`git`を実行し、標準出力を返す。失敗した場合はエラーの内容
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|err| format!("gitを実行できませんでした: {err}"))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// `dir`を含むリポジトリの最上位のフォルダ。`.git`のあるフォルダを上にたどって探す
+fn find_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// ファイルのパスをフォルダとファイル名に分ける
+fn split_path(file: &Path) -> Result<(&Path, &str), String> {
+    match (file.parent(), file.file_name().and_then(OsStr::to_str)) {
+        (Some(dir), Some(name)) => Ok((dir, name)),
+        _ => Err(format!("{}はファイルではありません", file.display())),
+    }
+}
+
+/// `width`の幅に切り詰め、足りない分は空白で埋める
+fn fit(text: &str, width: usize) -> String {
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result + &" ".repeat(width - used)
+}
+
+/// `git log --format=%x1e%H%x00%an%x00%ad%x00%s --name-only`の出力を読む
+fn parse_log(output: &str, root: &Path) -> Vec<Commit> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.split('\0');
+            let mut field = || fields.next().map(str::to_string);
+            let (hash, author, date, summary) = (field()?, field()?, field()?, field()?);
+            let path = lines.find(|line| !line.is_empty())?.to_string();
+
+            Some(Commit {
+                hash,
+                author,
+                date,
+                summary,
+                path,
+                root: root.to_path_buf(),
+            })
+        })
+        .collect()
+}
+
+/// `git blame --line-porcelain`の出力を読む
+fn parse_blame(output: &str) -> Vec<Blame> {
+    let mut blames = Vec::new();
+    let mut current = Blame {
+        hash: String::new(),
+        author: String::new(),
+        time: 0,
+    };
+    let mut header = true;
+
+    for line in output.lines() {
+        if header {
+            current.hash = line.split(' ').next().unwrap_or_default().to_string();
+            header = false;
+        } else if line.starts_with('\t') {
+            // 行の中身の後は次の行の見出しになる
+            blames.push(current.clone());
+            header = true;
+        } else if let Some(author) = line.strip_prefix("author ") {
+            current.author = author.to_string();
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            current.time = time.parse().unwrap_or(0);
+        }
+    }
+
+    blames
+}
+
+/// `git status --porcelain=v1 -z`の出力を読む
+fn parse(output: &[u8]) -> Vec<(PathBuf, Status)> {
+    let mut entries = Vec::new();
+    let mut fields = output.split(|&b| b == 0);
+
+    while let Some(field) = fields.next() {
+        // "XY パス"の形でないもの(最後の空欄)は飛ばす
+        if field.len() < 4 {
+            continue;
+        }
+        let (x, y) = (field[0], field[1]);
+        let path = String::from_utf8_lossy(&field[3..]);
+        let status = match (x, y) {
+            (b'!', b'!') => Status::Ignored,
+            (b'?', b'?') => Status::Untracked,
+            (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => Status::Conflicted,
+            (b'A' | b'R' | b'C', _) => Status::Added,
+            _ => Status::Modified,
+        };
+        // 名前の変更は元の名前が続く
+        if matches!(x, b'R' | b'C') {
+            fields.next();
+        }
+        entries.push((PathBuf::from(path.trim_end_matches('/')), status));
+    }
+
+    entries
+}
+
+#[test]
+fn git_status_test() {
+    let output = b" M src/main.rs\0A  src/new.rs\0?? notes/\0!! target/\0R  b.rs\0a\0UU c.rs\0";
+    let entries = parse(output);
+    assert_eq!(entries.len(), 6);
+    assert_eq!(entries[4], (PathBuf::from("b.rs"), Status::Added));
+
+    let git = GitStatus {
+        root: PathBuf::new(),
+        prefix: PathBuf::new(),
+        entries,
+    };
+    let get = |name: &str| git.get(OsStr::new(name));
+    assert_eq!(get("src"), Some(Status::Modified));
+    assert_eq!(get("notes"), Some(Status::Untracked));
+    assert_eq!(get("target"), Some(Status::Ignored));
+    assert_eq!(get("c.rs"), Some(Status::Conflicted));
+    assert_eq!(get("Cargo.toml"), None);
+
+    let git = GitStatus {
+        prefix: PathBuf::from("target"),
+        ..git
+    };
+    assert_eq!(git.get(OsStr::new("debug")), Some(Status::Ignored));
+}
+
+#[test]
+fn git_log_test() {
+    let log = "\x1eabc1234567\x00山田\x002024-01-31\x00設定を変更\n\nconf/a.toml\n\x1edef\x00B\x002023-12-01\x00init\n\na.toml\n";
+    let commits = parse_log(log, Path::new("/repo"));
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[0].short(), "abc1234");
+    assert_eq!(commits[0].path, "conf/a.toml");
+    assert_eq!(GitLog::format(&commits[1]), "def 2023-12-01 B init");
+
+    let blame = "abc1234567 1 1 2\nauthor 山田太郎\nauthor-time 0\nsummary x\n\tfn main() {\n\
+                 abc1234567 2 2\nauthor 山田太郎\nauthor-time 0\n\t}\n";
+    let blames = parse_blame(blame);
+    assert_eq!(blames.len(), 2);
+    assert_eq!(blames[1].hash, "abc1234567");
+    assert!(blames[0].label().starts_with("abc1234 山田太郎     "));
+}
+
+#[test]
+fn find_root_test() {
+    let root = std::env::temp_dir().join(format!("tui-cat-git-{}", std::process::id()));
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::create_dir_all(root.join("src/ui")).unwrap();
+
+    assert_eq!(find_root(&root.join("src/ui")), Some(root.clone()));
+
+    // 同じリポジトリの中なら読み込み直さずにフォルダだけ変える
+    let mut git = GitStatus {
+        root: root.clone(),
+        ..Default::default()
+    };
+    assert!(git.change_dir(&root.join("src")));
+    assert_eq!(git.prefix, PathBuf::from("src"));
+    assert!(!git.change_dir(&std::env::temp_dir()));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,255 @@
+use crate::components::hex;
+
+use encoding_rs::UTF_8;
+use ignore::WalkBuilder;
+use ratatui::widgets::ListState;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+/// 検索結果の最大件数
+const MAX_RESULTS: usize = 1000;
+/// 一致した行の表示文字数の上限
+const MAX_SNIPPET: usize = 200;
+/// これより大きいファイル(バイト)は検索しない
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+/// バイナリファイルかどうかを判定する際に調べる先頭の大きさ(バイト)
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+
+/// 一致した行
+#[derive(Debug, Clone)]
+pub struct GrepResult {
+    /// 一致したファイルのパス
+    pub path: PathBuf,
+    /// 行番号(0始まり)
+    pub line: usize,
+    /// 一致した行の中身
+    pub text: String,
+}
+
+/// ディレクトリ以下のファイルの中身の検索
+#[derive(Debug, Default)]
+pub struct Grep {
+    /// 検索文字列
+    pub query: String,
+    /// [true]正規表現 [false]文字列そのまま
+    pub regex: bool,
+    /// 検索文字列の入力中かどうか
+    pub input_mode: bool,
+    /// 検索結果を表示中かどうか
+    pub active: bool,
+    /// 検索を開始したフォルダ
+    pub root: PathBuf,
+    /// 検索結果
+    pub results: Vec<GrepResult>,
+    /// 検索結果の選択
+    pub state: ListState,
+    /// 正規表現のエラー
+    pub error: Option<String>,
+    /// 検索中のスレッドから届く検索結果。検索が終わったら[None]
+    receiver: Option<Receiver<GrepResult>>,
+    /// 検索を中止するかどうか
+    cancel: Arc<AtomicBool>,
+}
+
+impl Grep {
+    /// 検索結果を閉じる
+    pub fn close(&mut self) {
+        self.active = false;
+        self.input_mode = false;
+        self.stop();
+    }
+
+    /// `root`以下のファイルをバックグラウンドのスレッドで再帰的に検索する。<br>
+    /// 結果は[Grep::poll]で受け取る
+    pub fn run(&mut self, root: &Path) {
+        self.stop();
+        self.results.clear();
+        self.error = None;
+        self.root = root.to_path_buf();
+        self.active = true;
+
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+
+        match Regex::new(&pattern) {
+            Ok(re) => {
+                let (sender, receiver) = mpsc::channel();
+                let cancel = Arc::new(AtomicBool::new(false));
+                self.receiver = Some(receiver);
+                self.cancel = Arc::clone(&cancel);
+
+                let root = root.to_path_buf();
+                thread::spawn(move || walk(&root, &re, &sender, &cancel));
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+
+        self.state.select(None);
+    }
+
+    /// 検索中のスレッドから届いた結果を追加する。まだ検索中なら[true]
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+
+        let finished = loop {
+            match receiver.try_recv() {
+                Ok(result) => self.results.push(result),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished {
+            self.receiver = None;
+        }
+
+        if self.state.selected().is_none() && !self.results.is_empty() {
+            self.state.select(Some(0));
+        }
+        !finished
+    }
+
+    /// 検索中かどうか
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// 検索中のスレッドを中止する
+    fn stop(&mut self) {
+        self.cancel.store(true, Ordering::Release);
+        self.receiver = None;
+    }
+
+    /// 選択中の検索結果
+    pub fn selected(&self) -> Option<&GrepResult> {
+        self.results.get(self.state.selected()?)
+    }
+
+    /// 選択を一つ次に進める
+    pub fn next(&mut self) {
+        if self.results.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) if i + 1 < self.results.len() => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    /// 選択を一つ前に戻す
+    pub fn back(&mut self) {
+        if self.results.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => self.results.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    /// 検索結果の表示用文字列 例: "src/main.rs:10: fn main() {"
+    pub fn format(&self, result: &GrepResult) -> String {
+        let path = result.path.strip_prefix(&self.root).unwrap_or(&result.path);
+        format!("{}:{}: {}", path.display(), result.line + 1, result.text)
+    }
+
+    /// 検索結果の見出し
+    pub fn title(&self) -> String {
+        match &self.error {
+            Some(err) => format!("grep: {} (エラー: {})", self.query, err),
+            None if self.is_running() => {
+                format!("grep: {} (検索中... {}件)", self.query, self.results.len())
+            }
+            None => format!("grep: {} ({}件)", self.query, self.results.len()),
+        }
+    }
+}
+
+/// フォルダ以下を再帰的に調べ、`re`に一致する行を`sender`に送る。<br>
+/// 隠しファイル・フォルダと.gitignore・.ignoreで無視されるものは飛ばす
+fn walk(root: &Path, re: &Regex, sender: &Sender<GrepResult>, cancel: &AtomicBool) {
+    let walker = WalkBuilder::new(root)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    let mut count = 0;
+
+    for entry in walker.filter_map(Result::ok) {
+        if cancel.load(Ordering::Acquire) {
+            return;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Some(text) = read_text(entry.path()) else {
+            continue;
+        };
+
+        for (line, content) in text.lines().enumerate() {
+            if count >= MAX_RESULTS {
+                return;
+            }
+            if re.is_match(content) {
+                count += 1;
+                let result = GrepResult {
+                    path: entry.path().to_path_buf(),
+                    line,
+                    text: content.trim().chars().take(MAX_SNIPPET).collect(),
+                };
+                if sender.send(result).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// 検索するファイルの中身。<br>
+/// [MAX_FILE_SIZE]より大きいファイル・バイナリファイル・UTF-8で読めないファイルは[None]
+fn read_text(path: &Path) -> Option<String> {
+    if path.metadata().ok()?.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if hex::is_binary(&bytes[..bytes.len().min(BINARY_CHECK_SIZE)], UTF_8) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[test]
+fn grep_test() {
+    let root = std::env::current_dir().unwrap().join("src");
+
+    // このファイル自身に一致しないよう正規表現で探す
+    let mut grep = Grep {
+        query: "^読み込めたか[な]$".to_string(),
+        regex: true,
+        ..Default::default()
+    };
+    grep.run(&root);
+    while grep.poll() {
+        thread::yield_now();
+    }
+    assert_eq!(grep.results.len(), 1);
+    assert_eq!(grep.format(&grep.results[0]), "test.txt:4: 読み込めたかな");
+
+    // 不正な正規表現はエラーになる
+    grep.query = "(".to_string();
+    grep.run(&root);
+    assert!(grep.error.is_some());
+}
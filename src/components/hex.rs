@@ -0,0 +1,105 @@
+use crate::components::encoding;
+use encoding_rs::Encoding;
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// 一行に表示するバイト数
+pub const BYTES_PER_ROW: usize = 16;
+/// ファイルの先頭`buf`を調べ、バイナリファイルかどうかを判定する。<br>
+/// UTF-16以外でNULを含むか、制御文字が多い場合はバイナリとみなす
+pub fn is_binary(buf: &[u8], encoding: &'static Encoding) -> bool {
+    if encoding::is_utf16(encoding) {
+        return false;
+    }
+    if buf.contains(&0) {
+        return true;
+    }
+
+    // タブ・改行・改ページ・エスケープ以外の制御文字
+    let control = buf
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control * 10 > buf.len()
+}
+
+/// `size`バイトのファイルを表示するのに必要な行数
+#[inline]
+pub fn row_count(size: u64) -> usize {
+    (size as usize).div_ceil(BYTES_PER_ROW)
+}
+
+/// `start`行目から`rows`行分をオフセット・16進数・ASCIIの形式で読み込む
+/// # Example
+/// ```text
+/// 00000000  48 65 6c 6c 6f 0a                                 |Hello.|
+/// ```
+pub fn dump(path: &Path, start: usize, rows: usize) -> io::Result<Vec<Line<'static>>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start((start * BYTES_PER_ROW) as u64))?;
+
+    let mut buf = Vec::with_capacity(rows * BYTES_PER_ROW);
+    file.take((rows * BYTES_PER_ROW) as u64)
+        .read_to_end(&mut buf)?;
+
+    Ok(buf
+        .chunks(BYTES_PER_ROW)
+        .enumerate()
+        .map(|(i, bytes)| format_row((start + i) * BYTES_PER_ROW, bytes))
+        .collect())
+}
+
+/// 一行分のバイト列を表示用の[Line]にする
+fn format_row(offset: usize, bytes: &[u8]) -> Line<'static> {
+    let mut hex = String::with_capacity(BYTES_PER_ROW * 3 + 1);
+    for i in 0..BYTES_PER_ROW {
+        // 8バイトごとに区切る
+        if i == BYTES_PER_ROW / 2 {
+            hex.push(' ');
+        }
+        match bytes.get(i) {
+            Some(byte) => hex.push_str(&format!("{byte:02x} ")),
+            None => hex.push_str("   "),
+        }
+    }
+
+    let ascii: String = bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    Line::from(vec![
+        Span::styled(
+            format!("{offset:08x}  "),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::raw(hex),
+        Span::styled(format!(" |{ascii}|"), Style::default().fg(Color::Cyan)),
+    ])
+}
+
+#[test]
+fn format_row_test() {
+    let line = format_row(16, b"Hello\n");
+    let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(
+        text,
+        "00000010  48 65 6c 6c 6f 0a                                 |Hello.|"
+    );
+
+    assert_eq!(row_count(0), 0);
+    assert_eq!(row_count(17), 2);
+}
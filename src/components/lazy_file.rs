@@ -0,0 +1,248 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+};
+
+/// 行の位置を調べる際に一度に読み込む大きさ(バイト)
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 必要な行だけを読み込むファイル。<br>
+/// 各行の開始位置はバックグラウンドのスレッドで調べる
+#[derive(Debug)]
+pub struct LazyFile {
+    /// ファイルのパス
+    path: PathBuf,
+    /// ファイルの文字コード
+    encoding: &'static Encoding,
+    /// 各行の開始位置(バイト)
+    offsets: Arc<RwLock<Vec<u64>>>,
+    /// 行の位置を調べ終わったかどうか
+    indexed: Arc<AtomicBool>,
+    /// 行の位置の調査を中止するかどうか
+    cancel: Arc<AtomicBool>,
+}
+
+impl LazyFile {
+    /// ファイルを開き、行の位置の調査を開始する
+    pub fn open(path: &Path, encoding: &'static Encoding) -> io::Result<LazyFile> {
+        let file = File::open(path)?;
+
+        let lazy = LazyFile {
+            path: path.to_path_buf(),
+            encoding,
+            offsets: Arc::new(RwLock::new(Vec::new())),
+            indexed: Arc::new(AtomicBool::new(false)),
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+
+        let offsets = Arc::clone(&lazy.offsets);
+        let indexed = Arc::clone(&lazy.indexed);
+        let cancel = Arc::clone(&lazy.cancel);
+        thread::spawn(move || {
+            index_lines(file, &offsets, newline(encoding), &cancel);
+            indexed.store(true, Ordering::Release);
+        });
+
+        Ok(lazy)
+    }
+
+    /// 現在分かっている行数
+    pub fn line_count(&self) -> usize {
+        self.offsets.read().unwrap().len()
+    }
+
+    /// 行の位置を調べ終わったかどうか
+    pub fn is_indexed(&self) -> bool {
+        self.indexed.load(Ordering::Acquire)
+    }
+
+    /// `start`行目から`count`行を読み込む。<br>
+    /// 文字コードとして読めない部分は置き換え文字になる
+    pub fn read_lines(&self, start: usize, count: usize) -> io::Result<Vec<String>> {
+        let Some(&offset) = self.offsets.read().unwrap().get(start) else {
+            return Ok(Vec::new());
+        };
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        split_lines(BufReader::new(file), self.encoding)
+            .take(count)
+            .map(|line| line.map(|line| decode_line(&line, self.encoding)))
+            .collect()
+    }
+
+    /// ファイルの全ての行を先頭から順番に読み込む
+    pub fn lines(&self) -> io::Result<impl Iterator<Item = String> + Send> {
+        let file = File::open(&self.path)?;
+        let encoding = self.encoding;
+
+        Ok(split_lines(BufReader::new(file), encoding)
+            .map_while(Result::ok)
+            .map(move |line| decode_line(&line, encoding)))
+    }
+}
+
+impl Drop for LazyFile {
+    fn drop(&mut self) {
+        // 別のファイルを開いたら調査を中止する
+        self.cancel.store(true, Ordering::Release);
+    }
+}
+
+/// `encoding`での改行のバイト列。UTF-16は2バイトになる
+fn newline(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_16LE {
+        b"\n\0"
+    } else if encoding == UTF_16BE {
+        b"\0\n"
+    } else {
+        b"\n"
+    }
+}
+
+/// ファイルを少しずつ読み込み各行の開始位置を`offsets`に追加する。<br>
+/// 改行は`newline`の長さごとに区切った位置でだけ探す
+fn index_lines(mut file: File, offsets: &RwLock<Vec<u64>>, newline: &[u8], cancel: &AtomicBool) {
+    let unit = newline.len() as u64;
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut position: u64 = 0;
+    // 次の行が始まるかどうか
+    let mut line_start = true;
+    // 一つ前のバイト
+    let mut prev = 0;
+
+    while !cancel.load(Ordering::Acquire) {
+        let size = match file.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(size) => size,
+        };
+
+        let mut found = Vec::new();
+        for &byte in &buf[..size] {
+            let phase = position % unit;
+            if line_start && phase == 0 {
+                found.push(position);
+                line_start = false;
+            }
+            if phase == unit - 1 {
+                line_start = match newline {
+                    [newline] => byte == *newline,
+                    [first, second] => prev == *first && byte == *second,
+                    _ => false,
+                };
+            }
+            prev = byte;
+            position += 1;
+        }
+
+        offsets.write().unwrap().extend(found);
+    }
+}
+
+/// `reader`を`encoding`の改行で区切る。改行は含まない
+fn split_lines<R: BufRead + Send + 'static>(
+    reader: R,
+    encoding: &'static Encoding,
+) -> Box<dyn Iterator<Item = io::Result<Vec<u8>>> + Send> {
+    match newline(encoding) {
+        [newline] => Box::new(reader.split(*newline)),
+        newline => Box::new(UnitLines { reader, newline }),
+    }
+}
+
+/// 2バイトごとに改行を探して区切った行
+struct UnitLines<R> {
+    reader: R,
+    newline: &'static [u8],
+}
+
+impl<R: BufRead> Iterator for UnitLines<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = Vec::new();
+        let mut unit = [0; 2];
+        loop {
+            match self.reader.read_exact(&mut unit) {
+                Ok(()) if unit == self.newline => return Some(Ok(line)),
+                Ok(()) => line.extend_from_slice(&unit),
+                // 末尾の半端な1バイトは捨てる
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return (!line.is_empty()).then_some(Ok(line));
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// 一行分のバイト列を改行を除いた文字列にする
+fn decode_line(line: &[u8], encoding: &'static Encoding) -> String {
+    let carriage_return: &[u8] = match newline(encoding) {
+        [0, _] => b"\0\r",
+        [_, 0] => b"\r\0",
+        _ => b"\r",
+    };
+    let line = line.strip_suffix(carriage_return).unwrap_or(line);
+    encoding.decode_with_bom_removal(line).0.into_owned()
+}
+
+#[test]
+fn lazy_file_test() {
+    let path = std::env::current_dir().unwrap().join("src/test.txt");
+    let lazy = LazyFile::open(&path, encoding_rs::UTF_8).unwrap();
+
+    while !lazy.is_indexed() {
+        thread::yield_now();
+    }
+
+    assert_eq!(lazy.line_count(), 4);
+    assert_eq!(lazy.read_lines(3, 10).unwrap(), vec!["読み込めたかな"]);
+    assert_eq!(lazy.lines().unwrap().count(), 4);
+}
+
+#[test]
+fn lazy_file_utf16_test() {
+    let path = std::env::temp_dir().join(format!("tui-cat-utf16-{}.txt", std::process::id()));
+    for encoding in [UTF_16LE, UTF_16BE] {
+        // 2バイトの区切りをまたいで改行と同じバイト列が現れても行を区切らない
+        let text = "一行目\r\n\u{0a41}\u{3000}\u{4100}\u{0a41}\n三";
+        let mut bytes = if encoding == UTF_16LE {
+            vec![0xff, 0xfe]
+        } else {
+            vec![0xfe, 0xff]
+        };
+        for unit in text.encode_utf16() {
+            if encoding == UTF_16LE {
+                bytes.extend(unit.to_le_bytes());
+            } else {
+                bytes.extend(unit.to_be_bytes());
+            }
+        }
+        std::fs::write(&path, bytes).unwrap();
+
+        let lazy = LazyFile::open(&path, encoding).unwrap();
+        while !lazy.is_indexed() {
+            thread::yield_now();
+        }
+
+        assert_eq!(lazy.line_count(), 3);
+        assert_eq!(
+            lazy.read_lines(1, 10).unwrap(),
+            vec!["\u{0a41}\u{3000}\u{4100}\u{0a41}", "三"]
+        );
+        assert_eq!(
+            lazy.lines().unwrap().collect::<Vec<_>>(),
+            vec!["一行目", "\u{0a41}\u{3000}\u{4100}\u{0a41}", "三"]
+        );
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,476 @@
+use crate::{components::wrap::wrap, ui::syntax};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+use std::mem;
+use unicode_width::UnicodeWidthStr;
+
+/// 引用の"│"
+const QUOTE_STYLE: Style = Style::new().fg(Color::DarkGray);
+/// リストの"•"や"1."
+const BULLET_STYLE: Style = Style::new().fg(Color::LightBlue);
+/// `code`
+const CODE_STYLE: Style = Style::new().fg(Color::LightYellow);
+/// リンク
+const LINK_STYLE: Style = Style::new()
+    .fg(Color::LightCyan)
+    .add_modifier(Modifier::UNDERLINED);
+/// HTMLのタグ
+const HTML_STYLE: Style = Style::new().fg(Color::DarkGray);
+/// 表の罫線と水平線
+const BORDER_STYLE: Style = Style::new().fg(Color::DarkGray);
+
+/// Markdownを見出し・強調・リスト・引用・表・コードブロックを装飾した[Text]にする。<br>
+/// 文章は`width`で折り返し、コードブロックは[syntax::hylight]でハイライトする
+pub fn render(text: &str, width: usize, tab_width: usize, theme: &str) -> Text<'static> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+
+    let mut renderer = Renderer {
+        width: width.max(10),
+        tab_width,
+        theme,
+        lines: Vec::new(),
+        last_blank: false,
+        spans: Vec::new(),
+        styles: Vec::new(),
+        quote: 0,
+        lists: Vec::new(),
+        items: Vec::new(),
+        bullet: None,
+        code: None,
+        table: None,
+    };
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// 表の列の揃え方と、行ごと・セルごとの中身
+type Table = (Vec<Alignment>, Vec<Vec<Vec<Span<'static>>>>);
+
+/// [render]の途中の状態
+struct Renderer<'t> {
+    /// 折り返す幅
+    width: usize,
+    /// タブを何スペースに展開するか
+    tab_width: usize,
+    /// コードブロックのテーマ
+    theme: &'t str,
+    /// 出来上がった行
+    lines: Vec<Line<'static>>,
+    /// 最後の行が段落の区切りの空行か
+    last_blank: bool,
+    /// 折り返す前の、段落などの中身
+    spans: Vec<Span<'static>>,
+    /// 強調などの装飾。入れ子になる
+    styles: Vec<Style>,
+    /// 引用の深さ
+    quote: usize,
+    /// リストの入れ子。番号付きのリストは次の番号
+    lists: Vec<Option<u64>>,
+    /// リストの項目ごとの字下げの幅
+    items: Vec<usize>,
+    /// 次の行の先頭に付ける"•"や"1."
+    bullet: Option<String>,
+    /// コードブロックの言語と中身
+    code: Option<(String, String)>,
+    /// 表
+    table: Option<Table>,
+}
+
+impl Renderer<'_> {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match self.code.as_mut() {
+                Some((_, body)) => body.push_str(&text),
+                None => self.push(&text, self.style()),
+            },
+            Event::Code(code) | Event::InlineMath(code) | Event::DisplayMath(code) => {
+                self.push(&code, self.style().patch(CODE_STYLE))
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.push(&html, HTML_STYLE),
+            Event::FootnoteReference(name) => {
+                self.push(&format!("[^{name}]"), self.style().patch(LINK_STYLE))
+            }
+            Event::SoftBreak => self.push(" ", self.style()),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                let mut line = self.prefix();
+                line.push(Span::styled(
+                    "─".repeat(self.width.saturating_sub(self.prefix_width())),
+                    BORDER_STYLE,
+                ));
+                self.line(line);
+                self.blank();
+            }
+            Event::TaskListMarker(done) => {
+                self.push(if done { "[x] " } else { "[ ] " }, BULLET_STYLE)
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.styles.push(heading_style(level));
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .unwrap_or("")
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::HtmlBlock => self.flush(),
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(num)) => {
+                        *num += 1;
+                        format!("{}. ", *num - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.items.push(bullet.width());
+                self.bullet = Some(bullet);
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.table = Some((alignments, Vec::new()));
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some((_, rows)) = self.table.as_mut() {
+                    rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|(_, rows)| rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(Style::new().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::new().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.push_style(Style::new().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Superscript | Tag::Subscript => self.push_style(Style::new()),
+            Tag::Link { .. } => self.push_style(LINK_STYLE),
+            Tag::Image { .. } => {
+                self.push_style(LINK_STYLE);
+                self.push("画像: ", self.style());
+            }
+            Tag::Paragraph
+            | Tag::FootnoteDefinition(_)
+            | Tag::DefinitionList
+            | Tag::DefinitionListTitle
+            | Tag::DefinitionListDefinition
+            | Tag::MetadataBlock(_) => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush();
+                // 詰めて書かれたリストの項目の間には空行を入れない
+                if self.items.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.styles.pop();
+                self.blank();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                // 引用の最後の空行は引用の外に出す
+                if self.last_blank {
+                    self.lines.pop();
+                    self.last_blank = false;
+                }
+                self.quote -= 1;
+                self.blank();
+            }
+            TagEnd::CodeBlock => {
+                let Some((lang, body)) = self.code.take() else {
+                    return;
+                };
+                let syntax = syntax::syntax_name(&lang).or(Some("Plain Text"));
+                let text = syntax::hylight(body, syntax, self.tab_width, self.theme);
+                for line in text.lines {
+                    let mut spans = self.prefix();
+                    spans.push(Span::raw("  "));
+                    spans.extend(line.spans);
+                    self.line(spans);
+                }
+                self.blank();
+            }
+            TagEnd::HtmlBlock => {
+                self.flush();
+                self.blank();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.items.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.items.pop();
+            }
+            TagEnd::Table => {
+                self.render_table();
+                self.blank();
+            }
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.styles.pop();
+            }
+            TagEnd::TableHead
+            | TagEnd::TableRow
+            | TagEnd::TableCell
+            | TagEnd::FootnoteDefinition
+            | TagEnd::DefinitionList
+            | TagEnd::DefinitionListTitle
+            | TagEnd::DefinitionListDefinition
+            | TagEnd::MetadataBlock(_) => {}
+        }
+    }
+
+    /// 今の装飾
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    /// 今の装飾に`style`を重ねたものを追加する
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    /// 文字列を追加する。表の中であればセルに追加する。<br>
+    /// HTMLに含まれる改行はそのまま改行にする
+    fn push(&mut self, text: &str, style: Style) {
+        if let Some(cell) = self
+            .table
+            .as_mut()
+            .and_then(|(_, rows)| rows.last_mut())
+            .and_then(|row| row.last_mut())
+        {
+            cell.push(Span::styled(text.replace('\n', " "), style));
+            return;
+        }
+
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush();
+            }
+            if !part.is_empty() {
+                self.spans.push(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    /// 引用の"│"とリストの字下げ
+    fn prefix(&self) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        if self.quote > 0 {
+            spans.push(Span::styled("│ ".repeat(self.quote), QUOTE_STYLE));
+        }
+        let indent: usize = self.items.iter().sum();
+        if indent > 0 {
+            spans.push(Span::raw(" ".repeat(indent)));
+        }
+        spans
+    }
+
+    /// [Renderer::prefix]の幅
+    fn prefix_width(&self) -> usize {
+        self.quote * 2 + self.items.iter().sum::<usize>()
+    }
+
+    /// 溜まっている中身を折り返して行にする
+    fn flush(&mut self) {
+        let bullet = self.bullet.take();
+        if self.spans.is_empty() && bullet.is_none() {
+            return;
+        }
+
+        let spans = mem::take(&mut self.spans);
+        let width = self.width.saturating_sub(self.prefix_width()).max(1);
+        for (i, wrapped) in wrap(spans, width, true).into_iter().enumerate() {
+            let mut line = self.prefix();
+            // 一行目は字下げの最後を"•"に置き換える
+            if let (0, Some(bullet)) = (i, &bullet) {
+                let indent: usize = self.items.iter().sum();
+                line.pop();
+                if indent > bullet.width() {
+                    line.push(Span::raw(" ".repeat(indent - bullet.width())));
+                }
+                line.push(Span::styled(bullet.clone(), BULLET_STYLE));
+            }
+            line.extend(wrapped);
+            self.line(line);
+        }
+    }
+
+    /// 行を追加する
+    fn line(&mut self, spans: Vec<Span<'static>>) {
+        self.lines.push(Line::from(spans));
+        self.last_blank = false;
+    }
+
+    /// 段落の区切りの空行を追加する。続けては追加しない
+    fn blank(&mut self) {
+        if self.lines.is_empty() || self.last_blank {
+            return;
+        }
+        let prefix = self.prefix().into_iter().take(usize::from(self.quote > 0));
+        self.lines.push(Line::from(prefix.collect::<Vec<Span>>()));
+        self.last_blank = true;
+    }
+
+    /// 列の幅を揃えて表を行にする。一行目は見出しとして太字にする
+    fn render_table(&mut self) {
+        let Some((alignments, rows)) = self.table.take() else {
+            return;
+        };
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                rows.iter()
+                    .filter_map(|row| row.get(c))
+                    .map(|cell| spans_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let mut line = self.prefix();
+            let mut cells = row.into_iter();
+            for (c, &width) in widths.iter().enumerate() {
+                if c > 0 {
+                    line.push(Span::styled(" │ ", BORDER_STYLE));
+                }
+
+                let cell = cells.next().unwrap_or_default();
+                let space = width - spans_width(&cell);
+                let (left, right) = match alignments.get(c) {
+                    Some(Alignment::Right) => (space, 0),
+                    Some(Alignment::Center) => (space / 2, space - space / 2),
+                    _ => (0, space),
+                };
+
+                line.push(Span::raw(" ".repeat(left)));
+                for span in cell {
+                    line.push(if i == 0 {
+                        span.patch_style(Style::new().add_modifier(Modifier::BOLD))
+                    } else {
+                        span
+                    });
+                }
+                line.push(Span::raw(" ".repeat(right)));
+            }
+            self.line(line);
+
+            if i == 0 {
+                let mut separator = self.prefix();
+                let border: Vec<String> = widths.iter().map(|&w| "─".repeat(w)).collect();
+                separator.push(Span::styled(border.join("─┼─"), BORDER_STYLE));
+                self.line(separator);
+            }
+        }
+    }
+
+    fn finish(mut self) -> Text<'static> {
+        self.flush();
+        if self.last_blank {
+            self.lines.pop();
+        }
+        Text::from(self.lines)
+    }
+}
+
+/// 見出しの大きさごとの装飾
+fn heading_style(level: HeadingLevel) -> Style {
+    let style = Style::new().add_modifier(Modifier::BOLD);
+    match level {
+        HeadingLevel::H1 => style
+            .fg(Color::LightMagenta)
+            .add_modifier(Modifier::UNDERLINED),
+        HeadingLevel::H2 => style.fg(Color::LightCyan),
+        HeadingLevel::H3 => style.fg(Color::LightGreen),
+        _ => style,
+    }
+}
+
+/// spanの表示幅の合計
+fn spans_width(spans: &[Span]) -> usize {
+    spans.iter().map(|span| span.content.width()).sum()
+}
+
+#[test]
+fn render_test() {
+    let text = render(
+        "# 見出し\n\n本文の *強調* と `code` です。\n\n- 一つ目\n- 二つ目\n  1. 入れ子\n\n> 引用\n\n| a | bb |\n|---|---:|\n| ccc | d |\n\n```rust\nfn main() {}\n```\n",
+        40,
+        4,
+        syntax::DEFAULT_THEME,
+    );
+    let lines: Vec<String> = text
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            "見出し",
+            "",
+            "本文の 強調 と code です。",
+            "",
+            "• 一つ目",
+            "• 二つ目",
+            "  1. 入れ子",
+            "",
+            "│ 引用",
+            "",
+            "a   │ bb",
+            "────┼───",
+            "ccc │  d",
+            "",
+            "  fn main() {}",
+        ]
+    );
+}
@@ -1 +1,15 @@
 pub mod check_property;
+pub mod diff;
+pub mod encoding;
+pub mod filter;
+pub mod git;
+pub mod grep;
+pub mod hex;
+pub mod lazy_file;
+pub mod markdown;
+pub mod search;
+pub mod sort;
+pub mod symlink;
+pub mod table;
+pub mod tree;
+pub mod wrap;
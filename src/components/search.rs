@@ -0,0 +1,332 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+/// 保持する一致箇所の最大件数
+const MAX_MATCHES: usize = 10000;
+
+/// 一致箇所のハイライト色
+const MATCH_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+/// 現在選択中の一致箇所のハイライト色
+const CURRENT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::LightRed);
+
+/// ファイル内検索の一致箇所
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// 行番号(0始まり)
+    pub line: usize,
+    /// 行内の開始位置(バイト)
+    pub start: usize,
+    /// 行内の終了位置(バイト)
+    pub end: usize,
+}
+
+/// ファイル内検索の状態
+#[derive(Debug, Default)]
+pub struct Search {
+    /// 検索文字列
+    pub query: String,
+    /// 一致箇所の一覧
+    pub matches: Vec<Match>,
+    /// 現在選択中の一致箇所
+    pub current: Option<usize>,
+    /// 検索文字列の入力中かどうか
+    pub input_mode: bool,
+    /// 検索開始時のスクロール位置(キャンセル時に戻す)
+    pub origin: (usize, u16),
+    /// バックグラウンドで検索中のスレッドから届く一致箇所。検索が終わったら[None]
+    receiver: Option<Receiver<Match>>,
+    /// バックグラウンドの検索を中止するかどうか
+    cancel: Arc<AtomicBool>,
+}
+
+impl Search {
+    /// 検索状態を初期化する
+    pub fn clear(&mut self) {
+        self.stop();
+        *self = Search::default();
+    }
+
+    /// 各行から[Search::query]を探し[Search::matches]を更新する
+    pub fn find<I, S>(&mut self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.matches.clear();
+        self.current = None;
+
+        if self.query.is_empty() {
+            return;
+        }
+
+        for (num, line) in lines.into_iter().enumerate() {
+            for (start, s) in line.as_ref().match_indices(self.query.as_str()) {
+                if self.matches.len() >= MAX_MATCHES {
+                    return;
+                }
+                self.matches.push(Match {
+                    line: num,
+                    start,
+                    end: start + s.len(),
+                });
+            }
+        }
+    }
+
+    /// [Search::find]をバックグラウンドのスレッドで行う。<br>
+    /// 一致箇所は[Search::poll]で受け取る
+    pub fn find_background<I>(&mut self, lines: I)
+    where
+        I: Iterator<Item = String> + Send + 'static,
+    {
+        self.stop();
+        self.matches.clear();
+        self.current = None;
+
+        if self.query.is_empty() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.receiver = Some(receiver);
+        self.cancel = Arc::clone(&cancel);
+
+        let query = self.query.clone();
+        thread::spawn(move || {
+            let mut count = 0;
+            for (num, line) in lines.enumerate() {
+                if cancel.load(Ordering::Acquire) {
+                    return;
+                }
+                for (start, s) in line.match_indices(query.as_str()) {
+                    let m = Match {
+                        line: num,
+                        start,
+                        end: start + s.len(),
+                    };
+                    if count >= MAX_MATCHES || sender.send(m).is_err() {
+                        return;
+                    }
+                    count += 1;
+                }
+            }
+        });
+    }
+
+    /// バックグラウンドの検索から届いた一致箇所を追加する。まだ検索中なら[true]
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+
+        let finished = loop {
+            match receiver.try_recv() {
+                Ok(m) => self.matches.push(m),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished {
+            self.receiver = None;
+        }
+        !finished
+    }
+
+    /// バックグラウンドで検索中かどうか
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// バックグラウンドの検索を中止する
+    fn stop(&mut self) {
+        self.cancel.store(true, Ordering::Release);
+        self.receiver = None;
+    }
+
+    /// 指定した行以降で最初の一致箇所を選択する。
+    /// 見つからなければ先頭に戻る
+    pub fn select_from(&mut self, line: usize) -> Option<Match> {
+        if self.matches.is_empty() {
+            self.current = None;
+            return None;
+        }
+
+        let i = self
+            .matches
+            .iter()
+            .position(|m| m.line >= line)
+            .unwrap_or(0);
+        self.current = Some(i);
+        Some(self.matches[i])
+    }
+
+    /// 次の一致箇所を選択する。末尾まで来たら先頭に戻る
+    pub fn next(&mut self) -> Option<Match> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+
+        let i = match self.current {
+            Some(i) if i + 1 < len => i + 1,
+            Some(_) => 0,
+            None => 0,
+        };
+        self.current = Some(i);
+        Some(self.matches[i])
+    }
+
+    /// 前の一致箇所を選択する。先頭まで来たら末尾に戻る
+    pub fn prev(&mut self) -> Option<Match> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+
+        let i = match self.current {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.current = Some(i);
+        Some(self.matches[i])
+    }
+
+    /// 検索結果の表示用文字列 例: "検索: foo [2/5]"
+    pub fn status(&self) -> String {
+        let position = match self.current {
+            Some(i) => i + 1,
+            None => 0,
+        };
+        // 上限で打ち切った場合は件数に"+"を付ける
+        let more = if self.matches.len() >= MAX_MATCHES {
+            "+"
+        } else {
+            ""
+        };
+        let running = if self.is_running() {
+            " 検索中..."
+        } else {
+            ""
+        };
+        format!(
+            "検索: {} [{}/{}{more}]{running}",
+            self.query,
+            position,
+            self.matches.len()
+        )
+    }
+
+    /// 一致箇所をハイライトした行を返す。
+    /// `skip`個の先頭spanは検索対象外(行番号など)として扱う
+    pub fn highlight_line<'a>(&self, num: usize, line: &Line<'a>, skip: usize) -> Line<'a> {
+        let ranges: Vec<(usize, usize, Style)> = self
+            .matches
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.line == num)
+            .map(|(i, m)| {
+                let style = if Some(i) == self.current {
+                    CURRENT_STYLE
+                } else {
+                    MATCH_STYLE
+                };
+                (m.start, m.end, style)
+            })
+            .collect();
+
+        if ranges.is_empty() {
+            return line.clone();
+        }
+
+        let mut spans: Vec<Span<'a>> = line.spans.iter().take(skip).cloned().collect();
+        let mut offset = 0;
+
+        for span in line.spans.iter().skip(skip) {
+            let content = span.content.as_ref();
+            let span_end = offset + content.len();
+            let mut pos = offset;
+
+            for &(start, end, style) in &ranges {
+                if end <= pos || start >= span_end {
+                    continue;
+                }
+                let start = start.max(pos);
+                let end = end.min(span_end);
+
+                if start > pos {
+                    spans.push(Span::styled(
+                        content[pos - offset..start - offset].to_string(),
+                        span.style,
+                    ));
+                }
+                spans.push(Span::styled(
+                    content[start - offset..end - offset].to_string(),
+                    span.style.patch(style),
+                ));
+                pos = end;
+            }
+
+            if pos < span_end {
+                spans.push(Span::styled(
+                    content[pos - offset..].to_string(),
+                    span.style,
+                ));
+            }
+            offset = span_end;
+        }
+
+        Line::from(spans)
+    }
+}
+
+#[test]
+fn search_wrap_test() {
+    let mut search = Search {
+        query: "ab".to_string(),
+        ..Default::default()
+    };
+    search.find(["ab ab", "xx", "cab"]);
+    assert_eq!(search.matches.len(), 3);
+
+    assert_eq!(search.select_from(1).map(|m| m.line), Some(2));
+    // 末尾から先頭へ戻る
+    assert_eq!(search.next().map(|m| (m.line, m.start)), Some((0, 0)));
+    // 先頭から末尾へ戻る
+    assert_eq!(search.prev().map(|m| (m.line, m.start)), Some((2, 1)));
+
+    let line = Line::from(vec![Span::raw("c"), Span::raw("ab")]);
+    let highlighted = search.highlight_line(2, &line, 0);
+    assert_eq!(highlighted.spans.len(), 2);
+    assert_eq!(highlighted.spans[1].style, CURRENT_STYLE);
+}
+
+#[test]
+fn search_background_test() {
+    let mut search = Search {
+        query: "ab".to_string(),
+        ..Default::default()
+    };
+    let lines = ["ab", "x", "abab"].map(String::from).into_iter();
+    search.find_background(lines);
+    while search.poll() {
+        thread::yield_now();
+    }
+    assert_eq!(search.matches.len(), 3);
+    assert_eq!(search.status(), "検索: ab [0/3]");
+
+    // 上限を超えた分は保持しない
+    search.find((0..MAX_MATCHES + 5).map(|_| "ab"));
+    assert_eq!(search.matches.len(), MAX_MATCHES);
+    assert_eq!(search.status(), format!("検索: ab [0/{MAX_MATCHES}+]"));
+}
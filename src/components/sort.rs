@@ -0,0 +1,151 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+/// ファイル一覧を並べる基準
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// 名前(数字は数値として比べる)
+    #[default]
+    Name,
+    /// 大きさ
+    Size,
+    /// 最終更新日時
+    Modified,
+    /// 拡張子
+    Extension,
+}
+
+/// ファイル一覧の並べ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ListSort {
+    pub key: SortKey,
+    /// [true]の場合は逆順
+    pub reverse: bool,
+}
+
+impl ListSort {
+    /// 名前 → 大きさ → 更新日時 → 拡張子 の順に基準を切り替える
+    pub fn next_key(&mut self) {
+        self.key = match self.key {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::Modified,
+            SortKey::Modified => SortKey::Extension,
+            SortKey::Extension => SortKey::Name,
+        };
+    }
+
+    /// `paths`を並べ替える。基準が同じものは名前順にする
+    pub fn apply(&self, paths: &mut [PathBuf]) {
+        let name = |path: &PathBuf| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        paths.sort_by(|a, b| natural_cmp(&name(a), &name(b)));
+
+        match self.key {
+            SortKey::Name => {}
+            SortKey::Size => {
+                paths.sort_by_cached_key(|path| path.metadata().map_or(0, |m| m.len()))
+            }
+            SortKey::Modified => {
+                paths.sort_by_cached_key(|path| path.metadata().and_then(|m| m.modified()).ok())
+            }
+            SortKey::Extension => paths.sort_by_cached_key(|path| {
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_default()
+            }),
+        }
+
+        if self.reverse {
+            paths.reverse();
+        }
+    }
+
+    /// 画面下に表示する並べ方 例: "並べ替え: 名前↑"
+    pub fn label(&self) -> String {
+        let key = match self.key {
+            SortKey::Name => "名前",
+            SortKey::Size => "サイズ",
+            SortKey::Modified => "更新日時",
+            SortKey::Extension => "拡張子",
+        };
+        format!("並べ替え: {key}{}", if self.reverse { "↓" } else { "↑" })
+    }
+}
+
+/// 数字の並びを数値として比べる。それ以外は大文字・小文字を区別せずに比べる<br>
+/// 例: "file2" < "file10"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let x = take_number(&mut a_chars);
+                let y = take_number(&mut b_chars);
+                let (x_trim, y_trim) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                // 桁数が多い方が大きい。同じ桁数なら文字列として比べる
+                let ordering = x_trim
+                    .len()
+                    .cmp(&y_trim.len())
+                    .then_with(|| x_trim.cmp(y_trim));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+
+    // 大文字・小文字や先頭の0だけが違う場合も順番を決める
+    a.cmp(b)
+}
+
+#[test]
+fn natural_cmp_test() {
+    let mut names = vec![
+        "file10.txt",
+        "File2.txt",
+        "file1.txt",
+        "a",
+        "file02.txt",
+        "B",
+    ];
+    names.sort_by(|a, b| natural_cmp(a, b));
+    assert_eq!(
+        names,
+        vec![
+            "a",
+            "B",
+            "file1.txt",
+            "File2.txt",
+            "file02.txt",
+            "file10.txt"
+        ]
+    );
+    assert_eq!(natural_cmp("x", "x"), Ordering::Equal);
+
+    let mut sort = ListSort::default();
+    sort.next_key();
+    sort.reverse = true;
+    assert_eq!(sort.label(), "並べ替え: サイズ↓");
+}
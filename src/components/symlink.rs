@@ -0,0 +1,142 @@
+use ratatui::style::Color;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// シンボリックリンクの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    /// リンク先のファイル・フォルダがある
+    Ok,
+    /// リンク先が無い
+    Dangling,
+    /// リンク先がリンク自身を含むフォルダ(開けるが、たどり続けると同じ場所に戻る)か、
+    /// リンク同士が循環している(たどりきれないので開けない)
+    Cycle,
+}
+
+/// シンボリックリンク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symlink {
+    /// リンクに書かれているリンク先
+    pub target: PathBuf,
+    /// リンクをたどった先の絶対パス。リンク切れ・リンク同士の循環の場合は[None]
+    pub resolved: Option<PathBuf>,
+    pub state: LinkState,
+}
+
+impl Symlink {
+    /// `path`がシンボリックリンクなら読み込む。リンクでなければ[None]
+    pub fn read(path: &Path) -> Option<Symlink> {
+        if !path.symlink_metadata().ok()?.is_symlink() {
+            return None;
+        }
+        let target = fs::read_link(path).ok()?;
+
+        let resolved = match path.canonicalize() {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                return Some(Symlink {
+                    target,
+                    resolved: None,
+                    state: if is_loop(&err) {
+                        LinkState::Cycle
+                    } else {
+                        LinkState::Dangling
+                    },
+                })
+            }
+        };
+
+        // リンクを含むフォルダか、その上のフォルダを指している
+        let cycle = resolved.is_dir()
+            && path
+                .parent()
+                .and_then(|parent| parent.canonicalize().ok())
+                .is_some_and(|parent| parent.starts_with(&resolved));
+
+        Some(Symlink {
+            target,
+            resolved: Some(resolved),
+            state: if cycle {
+                LinkState::Cycle
+            } else {
+                LinkState::Ok
+            },
+        })
+    }
+
+    /// 一覧やプロパティに表示するリンク先 例: "-> ../file (リンク切れ)"
+    pub fn label(&self) -> String {
+        let note = match self.state {
+            LinkState::Ok => "",
+            LinkState::Dangling => " (リンク切れ)",
+            LinkState::Cycle => " (循環)",
+        };
+        format!("-> {}{note}", self.target.display())
+    }
+
+    /// 一覧でリンクの名前に付ける色
+    pub fn color(&self) -> Color {
+        match self.state {
+            LinkState::Ok => Color::LightCyan,
+            LinkState::Dangling | LinkState::Cycle => Color::LightRed,
+        }
+    }
+}
+
+/// リンク同士が循環していて、たどりきれなかったエラー(ELOOP)か
+#[cfg(unix)]
+fn is_loop(err: &io::Error) -> bool {
+    #[cfg(target_os = "linux")]
+    const ELOOP: i32 = 40;
+    #[cfg(not(target_os = "linux"))]
+    const ELOOP: i32 = 62;
+
+    err.raw_os_error() == Some(ELOOP)
+}
+
+/// リンク同士が循環していて、たどりきれなかったエラーか
+#[cfg(not(unix))]
+fn is_loop(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_test() {
+    use std::os::unix::fs::symlink;
+
+    let root = std::env::temp_dir().join(format!("tui-cat-symlink-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("dir")).unwrap();
+    fs::write(root.join("file"), "text").unwrap();
+    symlink("file", root.join("ok")).unwrap();
+    symlink("missing", root.join("dangling")).unwrap();
+    symlink("..", root.join("dir/up")).unwrap();
+    symlink("loop_b", root.join("loop_a")).unwrap();
+    symlink("loop_a", root.join("loop_b")).unwrap();
+
+    assert_eq!(Symlink::read(&root.join("file")), None);
+
+    let ok = Symlink::read(&root.join("ok")).unwrap();
+    assert_eq!(ok.state, LinkState::Ok);
+    assert_eq!(ok.resolved, root.join("file").canonicalize().ok());
+    assert_eq!(ok.label(), "-> file");
+
+    let dangling = Symlink::read(&root.join("dangling")).unwrap();
+    assert_eq!(dangling.state, LinkState::Dangling);
+    assert_eq!(dangling.label(), "-> missing (リンク切れ)");
+
+    let up = Symlink::read(&root.join("dir/up")).unwrap();
+    assert_eq!(up.state, LinkState::Cycle);
+
+    // リンク同士の循環はリンク切れではなく循環として扱う
+    let mutual = Symlink::read(&root.join("loop_a")).unwrap();
+    assert_eq!(mutual.state, LinkState::Cycle);
+    assert_eq!(mutual.resolved, None);
+    assert_eq!(mutual.label(), "-> loop_b (循環)");
+
+    fs::remove_dir_all(&root).unwrap();
+}
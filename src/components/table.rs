@@ -0,0 +1,173 @@
+use std::{cmp::Ordering, path::Path};
+use unicode_width::UnicodeWidthStr;
+
+/// 列の幅の上限
+const MAX_WIDTH: usize = 40;
+
+/// CSV・TSVを表として表示するためのデータ
+#[derive(Debug)]
+pub struct CsvTable {
+    /// 一行目の見出し
+    pub header: Vec<String>,
+    /// 二行目以降
+    rows: Vec<Vec<String>>,
+    /// 表示する順番([CsvTable::rows]の位置)
+    order: Vec<usize>,
+    /// 各列の幅
+    pub widths: Vec<u16>,
+    /// 並べ替えている列と、降順かどうか
+    pub sort: Option<(usize, bool)>,
+}
+
+/// 拡張子かシンタックスの名前から区切り文字を判定する。CSV・TSVでなければ[None]
+pub fn delimiter(path: &Path, syntax: Option<&str>) -> Option<u8> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    match (extension.as_deref(), syntax) {
+        (Some("csv"), _) | (_, Some("CSV")) => Some(b','),
+        (Some("tsv" | "tab"), _) | (_, Some("TSV")) => Some(b'\t'),
+        _ => None,
+    }
+}
+
+impl CsvTable {
+    /// `text`を`delimiter`区切りとして解析する。""で囲まれた値の中の区切り文字や改行もそのまま読み込む
+    pub fn parse(text: &str, delimiter: u8) -> Result<CsvTable, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let mut records = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|err| err.to_string())?;
+            // 値の中の改行は一行で表示できるように空白にする
+            let cells = record
+                .iter()
+                .map(|cell| cell.lines().collect::<Vec<&str>>().join(" "));
+            records.push(cells.collect::<Vec<String>>());
+        }
+
+        let mut records = records.into_iter();
+        let header = records.next().unwrap_or_default();
+        let rows: Vec<Vec<String>> = records.collect();
+
+        // 列の幅は見出しと値の一番長いものに合わせる
+        let columns = rows.iter().map(Vec::len).fold(header.len(), usize::max);
+        let widths = (0..columns)
+            .map(|c| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .filter_map(|row| row.get(c))
+                    .map(|cell| cell.width())
+                    .max()
+                    .unwrap_or(0)
+                    .clamp(1, MAX_WIDTH) as u16
+            })
+            .collect();
+
+        Ok(CsvTable {
+            header,
+            order: (0..rows.len()).collect(),
+            rows,
+            widths,
+            sort: None,
+        })
+    }
+
+    /// 見出しを除いた行数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 列の数
+    #[inline]
+    pub fn columns(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// 表示する順番の行
+    pub fn rows(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.order.iter().map(|&i| &self.rows[i])
+    }
+
+    /// `column`列で並べ替える。同じ列で繰り返すと 昇順 → 降順 → 元の順 を切り替える。<br>
+    /// 両方数値の場合は数値として比べる
+    pub fn sort_by(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((c, false)) if c == column => Some((column, true)),
+            Some((c, true)) if c == column => None,
+            _ => Some((column, false)),
+        };
+
+        self.order = (0..self.rows.len()).collect();
+        if let Some((column, descending)) = self.sort {
+            let rows = &self.rows;
+            self.order.sort_by(|&a, &b| {
+                let a = rows[a].get(column).map_or("", String::as_str);
+                let b = rows[b].get(column).map_or("", String::as_str);
+                let ordering = compare(a, b);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+
+    /// 並べ替えの状態
+    /// # Example
+    /// ```text
+    /// 並べ替え: 名前 ↑
+    /// ```
+    pub fn sort_status(&self) -> Option<String> {
+        let (column, descending) = self.sort?;
+        let name = match self.header.get(column) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("{}列目", column + 1),
+        };
+        Some(format!(
+            "並べ替え: {name} {}",
+            if descending { "↓" } else { "↑" }
+        ))
+    }
+}
+
+/// 数値として読めれば数値、読めなければ文字列として比べる
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[test]
+fn csv_table_test() {
+    let mut table = CsvTable::parse(
+        "name,score\n\"Smith, J\",10\nあいう,9\n\"改\n行\",100\n",
+        b',',
+    )
+    .unwrap();
+    assert_eq!(table.header, vec!["name", "score"]);
+    assert_eq!(table.len(), 3);
+    assert_eq!(table.widths, vec![8, 5]);
+    assert_eq!(table.rows().nth(2).unwrap()[0], "改 行");
+
+    let first = |table: &CsvTable| table.rows().next().unwrap()[1].clone();
+    table.sort_by(1);
+    assert_eq!(first(&table), "9");
+    table.sort_by(1);
+    assert_eq!(first(&table), "100");
+    assert_eq!(table.sort_status().unwrap(), "並べ替え: score ↓");
+    table.sort_by(1);
+    assert_eq!(first(&table), "10");
+    assert_eq!(table.sort, None);
+
+    let table = CsvTable::parse("a\tb\n1\t2\t3\n", b'\t').unwrap();
+    assert_eq!(table.columns(), 3);
+}
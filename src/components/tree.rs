@@ -0,0 +1,451 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// 最初から開いておく深さ
+const EXPAND_DEPTH: usize = 2;
+
+/// キーの色
+const KEY_STYLE: Style = Style::new().fg(Color::LightBlue);
+/// 文字列の色
+const STRING_STYLE: Style = Style::new().fg(Color::LightGreen);
+/// 数値・真偽値の色
+const NUMBER_STYLE: Style = Style::new().fg(Color::LightYellow);
+/// nullと要素数の色
+const NULL_STYLE: Style = Style::new().fg(Color::DarkGray);
+/// カーソルのある行
+pub const CURSOR_STYLE: Style = Style::new()
+    .bg(Color::DarkGray)
+    .add_modifier(Modifier::BOLD);
+
+/// 木構造で表示できるファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// 拡張子かシンタックスの名前から形式を判定する
+    pub fn detect(path: &Path, syntax: Option<&str>) -> Option<Format> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match (extension.as_deref(), syntax) {
+            (Some("json"), _) | (_, Some("JSON")) => Some(Format::Json),
+            (Some("yaml" | "yml"), _) | (_, Some("YAML")) => Some(Format::Yaml),
+            (Some("toml"), _) | (_, Some("TOML")) => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// ノードの値
+#[derive(Debug)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<Node>),
+}
+
+/// 木構造の一つのノード
+#[derive(Debug)]
+struct Node {
+    /// オブジェクトのキー。配列の要素は[None]
+    key: Option<String>,
+    value: Value,
+    /// 子要素を表示しているか
+    expanded: bool,
+}
+
+impl Node {
+    fn new(key: Option<String>, value: Value) -> Node {
+        Node {
+            key,
+            value,
+            expanded: false,
+        }
+    }
+
+    /// 子要素。配列・オブジェクト以外は空
+    fn children(&self) -> &[Node] {
+        match &self.value {
+            Value::Array(children) | Value::Object(children) => children,
+            _ => &[],
+        }
+    }
+
+    fn children_mut(&mut self) -> &mut [Node] {
+        match &mut self.value {
+            Value::Array(children) | Value::Object(children) => children,
+            _ => &mut [],
+        }
+    }
+
+    /// 開閉できるか
+    fn is_container(&self) -> bool {
+        matches!(self.value, Value::Array(_) | Value::Object(_))
+    }
+
+    /// `depth`より浅いノードを開く
+    fn expand_to(&mut self, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        self.expanded = true;
+        for child in self.children_mut() {
+            child.expand_to(depth - 1);
+        }
+    }
+}
+
+/// JSON・YAML・TOMLを開閉できる木構造で表示する
+#[derive(Debug)]
+pub struct Tree {
+    root: Node,
+    /// 表示している行のノードの位置(根からの子要素の番号の並び)
+    rows: Vec<Vec<usize>>,
+    /// カーソルのある行
+    pub cursor: usize,
+}
+
+impl Tree {
+    /// `text`を`format`として解析する
+    pub fn parse(text: &str, format: Format) -> Result<Tree, String> {
+        let value = match format {
+            Format::Json => serde_json::from_str::<serde_json::Value>(text)
+                .map(from_json)
+                .map_err(|err| err.to_string())?,
+            Format::Yaml => {
+                // 複数のドキュメントがある場合は配列にする
+                let mut docs = Vec::new();
+                for doc in serde_yaml::Deserializer::from_str(text) {
+                    let value = serde_yaml::Value::deserialize(doc).map_err(|e| e.to_string())?;
+                    docs.push(Node::new(None, from_yaml(value)));
+                }
+                match docs.len() {
+                    1 => docs.pop().unwrap().value,
+                    _ => Value::Array(docs),
+                }
+            }
+            Format::Toml => text
+                .parse::<toml::Table>()
+                .map(|table| from_toml(toml::Value::Table(table)))
+                .map_err(|err| err.to_string())?,
+        };
+
+        let mut root = Node::new(None, value);
+        root.expand_to(EXPAND_DEPTH);
+
+        let mut tree = Tree {
+            root,
+            rows: Vec::new(),
+            cursor: 0,
+        };
+        tree.update_rows();
+        Ok(tree)
+    }
+
+    /// カーソルを一つ上に移動する
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// カーソルを一つ下に移動する
+    pub fn down(&mut self) {
+        if self.cursor + 1 < self.rows.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// カーソルのノードを開閉する
+    pub fn toggle(&mut self) {
+        let node = self.node_mut(self.cursor);
+        if node.is_container() {
+            node.expanded = !node.expanded;
+            self.update_rows();
+        }
+    }
+
+    /// カーソルのノードを開く。開いている場合は最初の子要素に移動する
+    pub fn expand(&mut self) {
+        let node = self.node_mut(self.cursor);
+        if !node.is_container() {
+            return;
+        }
+        if node.expanded {
+            self.down();
+        } else {
+            node.expanded = true;
+            self.update_rows();
+        }
+    }
+
+    /// カーソルのノードを閉じる。閉じている場合は親に移動する
+    pub fn collapse(&mut self) {
+        let node = self.node_mut(self.cursor);
+        if node.is_container() && node.expanded {
+            node.expanded = false;
+            self.update_rows();
+            return;
+        }
+
+        let mut parent = self.rows[self.cursor].clone();
+        if parent.pop().is_some() {
+            if let Some(row) = self.rows.iter().position(|row| *row == parent) {
+                self.cursor = row;
+            }
+        }
+    }
+
+    /// カーソルのノードのJSONパス
+    /// # Example
+    /// ```text
+    /// $.dependencies["serde-json"].features[0]
+    /// ```
+    pub fn path(&self) -> String {
+        let mut path = "$".to_string();
+        let mut node = &self.root;
+
+        for &i in self.rows.get(self.cursor).into_iter().flatten() {
+            let child = &node.children()[i];
+            match &child.key {
+                Some(key) if is_identifier(key) => path += &format!(".{key}"),
+                Some(key) => path += &format!("[{key:?}]"),
+                None => path += &format!("[{i}]"),
+            }
+            node = child;
+        }
+
+        path
+    }
+
+    /// 表示する行。カーソルのある行は強調する
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(row, path)| {
+                let line = self.line(path);
+                if row == self.cursor {
+                    line.patch_style(CURSOR_STYLE)
+                } else {
+                    line
+                }
+            })
+            .collect()
+    }
+
+    /// 一つのノードを表示する行
+    /// # Example
+    /// ```text
+    /// ▼ dependencies {3}
+    ///     name: "tui-cat"
+    /// ```
+    fn line(&self, path: &[usize]) -> Line<'static> {
+        let node = self.node(path);
+        let mut spans = vec![Span::raw("  ".repeat(path.len()))];
+
+        spans.push(Span::raw(match (node.is_container(), node.expanded) {
+            (false, _) => "  ",
+            (true, true) => "▼ ",
+            (true, false) => "▶ ",
+        }));
+
+        // 根はキーを表示しない
+        if let Some(&i) = path.last() {
+            let key = match &node.key {
+                Some(key) => key.clone(),
+                None => format!("[{i}]"),
+            };
+            spans.push(Span::styled(key, KEY_STYLE));
+            spans.push(Span::raw(if node.is_container() { " " } else { ": " }));
+        }
+
+        spans.push(match &node.value {
+            Value::Null => Span::styled("null", NULL_STYLE),
+            Value::Bool(b) => Span::styled(b.to_string(), NUMBER_STYLE),
+            Value::Number(n) => Span::styled(n.clone(), NUMBER_STYLE),
+            Value::String(s) => Span::styled(format!("{s:?}"), STRING_STYLE),
+            Value::Array(children) => Span::styled(format!("[{}]", children.len()), NULL_STYLE),
+            Value::Object(children) => Span::styled(format!("{{{}}}", children.len()), NULL_STYLE),
+        });
+
+        Line::from(spans)
+    }
+
+    fn node(&self, path: &[usize]) -> &Node {
+        path.iter().fold(&self.root, |node, &i| &node.children()[i])
+    }
+
+    fn node_mut(&mut self, row: usize) -> &mut Node {
+        let path = self.rows[row].clone();
+        path.iter()
+            .fold(&mut self.root, |node, &i| &mut node.children_mut()[i])
+    }
+
+    /// 開いているノードから表示する行を作り直す。カーソルは同じノードに置く
+    fn update_rows(&mut self) {
+        let current = self.rows.get(self.cursor).cloned();
+
+        let mut rows = Vec::new();
+        let mut stack = vec![Vec::new()];
+        while let Some(path) = stack.pop() {
+            let node = self.node(&path);
+            if node.expanded {
+                for i in (0..node.children().len()).rev() {
+                    let mut child = path.clone();
+                    child.push(i);
+                    stack.push(child);
+                }
+            }
+            rows.push(path);
+        }
+
+        self.cursor = current
+            .and_then(|current| rows.iter().position(|row| *row == current))
+            .unwrap_or(0);
+        self.rows = rows;
+    }
+}
+
+/// JSONパスで"."の後にそのまま書けるキーか
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn from_json(value: serde_json::Value) -> Value {
+    use serde_json::Value as Json;
+
+    match value {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => Value::Number(n.to_string()),
+        Json::String(s) => Value::String(s),
+        Json::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|v| Node::new(None, from_json(v)))
+                .collect(),
+        ),
+        Json::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| Node::new(Some(k), from_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_yaml(value: serde_yaml::Value) -> Value {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => Value::Number(n.to_string()),
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(seq) => Value::Array(
+            seq.into_iter()
+                .map(|v| Node::new(None, from_yaml(v)))
+                .collect(),
+        ),
+        Yaml::Mapping(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    // 文字列以外のキーはYAMLの表記にする
+                    let key = match k {
+                        Yaml::String(s) => s,
+                        k => serde_yaml::to_string(&k)
+                            .map(|s| s.trim_end().to_string())
+                            .unwrap_or_default(),
+                    };
+                    Node::new(Some(key), from_yaml(v))
+                })
+                .collect(),
+        ),
+        Yaml::Tagged(tagged) => from_yaml(tagged.value),
+    }
+}
+
+fn from_toml(value: toml::Value) -> Value {
+    use toml::Value as Toml;
+
+    match value {
+        Toml::String(s) => Value::String(s),
+        Toml::Integer(i) => Value::Number(i.to_string()),
+        Toml::Float(f) => Value::Number(f.to_string()),
+        Toml::Boolean(b) => Value::Bool(b),
+        Toml::Datetime(d) => Value::Number(d.to_string()),
+        Toml::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|v| Node::new(None, from_toml(v)))
+                .collect(),
+        ),
+        Toml::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| Node::new(Some(k), from_toml(v)))
+                .collect(),
+        ),
+    }
+}
+
+#[test]
+fn tree_test() {
+    let plain = |tree: &Tree| -> Vec<String> {
+        tree.lines()
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    };
+
+    let mut tree = Tree::parse(
+        r#"{"name":"tui-cat","deps":{"serde-json":{"features":["a"]}},"n":null}"#,
+        Format::Json,
+    )
+    .unwrap();
+    assert_eq!(
+        plain(&tree),
+        vec![
+            "▼ {3}",
+            "    name: \"tui-cat\"",
+            "  ▼ deps {1}",
+            "    ▶ serde-json {1}",
+            "    n: null",
+        ]
+    );
+
+    tree.down();
+    tree.down();
+    tree.down();
+    assert_eq!(tree.path(), "$.deps[\"serde-json\"]");
+    tree.expand();
+    tree.expand();
+    tree.expand();
+    tree.down();
+    assert_eq!(tree.path(), "$.deps[\"serde-json\"].features[0]");
+
+    tree.collapse();
+    tree.collapse();
+    assert_eq!(tree.path(), "$.deps[\"serde-json\"].features");
+    assert_eq!(tree.lines().len(), 6);
+
+    let tree = Tree::parse("a:\n  - 1\n  - true\n", Format::Yaml).unwrap();
+    assert_eq!(plain(&tree)[1], "  ▼ a [2]");
+
+    let tree = Tree::parse("[package]\nname = \"x\"\n", Format::Toml).unwrap();
+    assert_eq!(plain(&tree)[2], "      name: \"x\"");
+
+    assert!(Tree::parse("{", Format::Json).is_err());
+}
@@ -0,0 +1,163 @@
+use ratatui::{style::Style, text::Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// 表示欄の幅で折り返すかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// 折り返さない
+    #[default]
+    Off,
+    /// 単語の区切りで折り返す
+    Word,
+    /// どの文字の間でも折り返す
+    Char,
+}
+
+impl WrapMode {
+    /// 折り返さない → 単語 → 文字 の順に切り替える
+    pub fn next(self) -> WrapMode {
+        match self {
+            WrapMode::Off => WrapMode::Word,
+            WrapMode::Word => WrapMode::Char,
+            WrapMode::Char => WrapMode::Off,
+        }
+    }
+
+    /// タイトルに表示する名前
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::Off => "折り返しなし",
+            WrapMode::Word => "折り返し: 単語",
+            WrapMode::Char => "折り返し: 文字",
+        }
+    }
+}
+
+/// `width`で折り返す。<br>
+/// `words`が[true]の場合は英単語の途中では折り返さず(全角文字はどこでも折り返す)、行頭の空白は表示しない
+pub fn wrap<'a>(spans: Vec<Span<'a>>, width: usize, words: bool) -> Vec<Vec<Span<'a>>> {
+    let width = width.max(1);
+    let mut lines: Vec<Vec<Span>> = vec![Vec::new()];
+    let mut current = 0;
+
+    for span in spans {
+        for word in split_words(&span.content, words) {
+            let mut word = word.to_string();
+            let mut word_width = word.width();
+
+            if current + word_width > width && current > 0 {
+                lines.push(Vec::new());
+                current = 0;
+                // 行頭の空白は表示しない
+                if words && word.trim().is_empty() {
+                    continue;
+                }
+            }
+
+            // 一行に収まらない長い単語は途中で折り返す
+            while word_width > width - current {
+                let mut split = 0;
+                let mut split_width = 0;
+                for (i, c) in word.char_indices() {
+                    let w = c.width().unwrap_or(0);
+                    if current + split_width + w > width {
+                        break;
+                    }
+                    split = i + c.len_utf8();
+                    split_width += w;
+                }
+                if split == 0 && current == 0 {
+                    // 一文字も入らない場合は一文字だけ入れる
+                    split = word.chars().next().map_or(0, char::len_utf8);
+                }
+
+                let rest = word.split_off(split);
+                push_span(lines.last_mut().unwrap(), word, span.style);
+                lines.push(Vec::new());
+                current = 0;
+                word = rest;
+                word_width = word.width();
+            }
+
+            current += word_width;
+            push_span(lines.last_mut().unwrap(), word, span.style);
+        }
+    }
+
+    lines
+}
+
+/// 空白の並び・全角文字・それ以外の並び に分ける。`words`が[false]の場合は一文字ずつに分ける
+fn split_words(text: &str, words: bool) -> Vec<&str> {
+    if !words {
+        return text
+            .char_indices()
+            .map(|(i, c)| &text[i..i + c.len_utf8()])
+            .collect();
+    }
+
+    let kind = |c: char| {
+        if c.is_whitespace() {
+            0
+        } else if c.width() == Some(2) {
+            2
+        } else {
+            1
+        }
+    };
+
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut prev = None;
+    for (i, c) in text.char_indices() {
+        let k = kind(c);
+        if i > start && (prev != Some(k) || k == 2) {
+            words.push(&text[start..i]);
+            start = i;
+        }
+        prev = Some(k);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// 同じ装飾の場合は前のspanにつなげる
+fn push_span<'a>(line: &mut Vec<Span<'a>>, text: String, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match line.last_mut() {
+        Some(last) if last.style == style => last.content.to_mut().push_str(&text),
+        _ => line.push(Span::styled(text, style)),
+    }
+}
+
+#[test]
+fn wrap_test() {
+    let plain = |lines: Vec<Vec<Span>>| -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    };
+
+    assert_eq!(
+        plain(wrap(vec![Span::raw("hello world foo")], 11, true)),
+        vec!["hello world", "foo"]
+    );
+    assert_eq!(
+        plain(wrap(vec![Span::raw("hello world foo")], 8, false)),
+        vec!["hello wo", "rld foo"]
+    );
+    assert_eq!(
+        plain(wrap(vec![Span::raw("日本語の文章")], 8, true)),
+        vec!["日本語の", "文章"]
+    );
+    assert_eq!(
+        plain(wrap(vec![Span::raw("abcdefghij")], 4, true)),
+        vec!["abcd", "efgh", "ij"]
+    );
+    assert_eq!(wrap(Vec::new(), 4, true).len(), 1);
+}
@@ -0,0 +1,495 @@
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fmt,
+    fs::{self, read_to_string},
+    io::{self, ErrorKind},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use toml::Spanned;
+
+use crate::ui::{
+    events::{Key, Keymap},
+    syntax,
+};
+
+/// 設定ファイルの名前
+const FILE_NAME: &str = "config.toml";
+/// タブを展開するスペース数の範囲
+pub const TAB_WIDTH_RANGE: std::ops::RangeInclusive<usize> = 0..=16;
+
+/// 設定ファイルの名前と値。位置は誤りの行番号に使う
+type Entries = BTreeMap<Spanned<String>, Spanned<toml::Value>>;
+
+/// 設定ファイルのセクション
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Sections {
+    general: Entries,
+    keys: Entries,
+    syntax: Entries,
+}
+
+/// 設定ファイルから読み込んだ設定
+/// # Example
+/// ```toml
+/// # ~/.config/tui-cat/config.toml
+/// tab_width = 2
+/// line_numbers = true
+/// show_hidden = false
+/// respect_ignore = true
+/// theme = "InspiredGitHub"
+/// list_width = 30
+///
+/// [keys]
+/// up = "w,k"
+/// down = "s,j"
+///
+/// [syntax]
+/// "*.conf" = "bash"
+/// "Jenkinsfile" = "Groovy"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// タブを何スペースに展開するか
+    pub tab_width: usize,
+    /// ファイルを開いた時に行数を表示するか
+    pub line_numbers: bool,
+    /// 隠しファイルを表示するか
+    pub show_hidden: bool,
+    /// .gitignore・.ignoreで無視されるものを一覧から除くか
+    pub respect_ignore: bool,
+    /// シンタックスハイライトのテーマ
+    pub theme: String,
+    /// ファイル一覧の幅(%)
+    pub list_width: u16,
+    /// キーの割り当て
+    pub keymap: Keymap,
+    /// ファイル名のパターンとシンタックスの名前の対応
+    pub syntax_map: Vec<(String, &'static str)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            tab_width: 4,
+            line_numbers: false,
+            show_hidden: true,
+            respect_ignore: false,
+            theme: syntax::DEFAULT_THEME.to_string(),
+            list_width: 20,
+            keymap: Keymap::default(),
+            syntax_map: Vec::new(),
+        }
+    }
+}
+
+/// 設定ファイルの誤り
+#[derive(Debug)]
+pub struct ConfigError {
+    /// 行番号(1始まり)
+    pub line: usize,
+    /// 内容
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}行目: {}", self.line, self.message)
+    }
+}
+
+impl Config {
+    /// 設定フォルダのパスを取得する。<br>
+    /// `$XDG_CONFIG_HOME/tui-cat` → `~/.config/tui-cat` → `%APPDATA%\tui-cat` の順
+    pub fn dir() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+        Some(base.join("tui-cat"))
+    }
+
+    /// キャッシュを保存するフォルダのパスを取得する。<br>
+    /// `$XDG_CACHE_HOME/tui-cat` → `~/.cache/tui-cat` → `%LOCALAPPDATA%\tui-cat` の順
+    pub fn cache_dir() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+
+        Some(base.join("tui-cat"))
+    }
+
+    /// 設定ファイルを読み込む。ファイルが無ければ初期設定を返す
+    pub fn load() -> Result<Config, (PathBuf, Vec<ConfigError>)> {
+        let Some(path) = Config::dir().map(|dir| dir.join(FILE_NAME)) else {
+            return Ok(Config::default());
+        };
+
+        match read_to_string(&path) {
+            Ok(text) => Config::parse(&text).map_err(|err| (path, err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err((
+                path,
+                vec![ConfigError {
+                    line: 0,
+                    message: err.to_string(),
+                }],
+            )),
+        }
+    }
+
+    /// 設定ファイルの中身を解析する。誤りがあれば全て返す<br>
+    /// TOMLとして読めない場合は最初の誤りだけを返す
+    pub fn parse(text: &str) -> Result<Config, Vec<ConfigError>> {
+        let line_of = |span: Range<usize>| text[..span.start].matches('\n').count() + 1;
+        let parsed = toml::from_str::<Entries>(text)
+            .and_then(|top| Ok((top, toml::from_str::<Sections>(text)?)));
+        let (top, sections) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                return Err(vec![ConfigError {
+                    line: err.span().map_or(0, line_of),
+                    message: err.message().trim().replace('\n', " "),
+                }])
+            }
+        };
+
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+        let mut error = |span: Range<usize>, message: String| {
+            errors.push(ConfigError {
+                line: line_of(span),
+                message,
+            })
+        };
+
+        // セクションの外は[general]と同じ扱い
+        for (key, value) in in_order(top) {
+            let result = match (key.get_ref().as_str(), value.get_ref()) {
+                ("general" | "keys" | "syntax", toml::Value::Table(_)) => Ok(()),
+                (name, toml::Value::Table(_)) => Err(format!("不明なセクション [{name}]")),
+                (name, value) => config.set(name, &value_text(value)),
+            };
+            if let Err(message) = result {
+                error(key.span(), message);
+            }
+        }
+        for (key, value) in in_order(sections.general) {
+            if let Err(message) = config.set(key.get_ref(), &value_text(value.get_ref())) {
+                error(key.span(), message);
+            }
+        }
+
+        // 設定ファイルで割り当てた操作
+        let mut bound = HashSet::new();
+        // 他の操作から外した文字キー (位置, 操作名, 文字, 外された操作)
+        let mut taken = Vec::new();
+        for (key, value) in in_order(sections.keys) {
+            let name = key.get_ref();
+            let result = config
+                .set_key(name, value.get_ref())
+                .and_then(|(action, chars)| {
+                    bound.insert(action);
+                    for (c, other) in chars {
+                        // 設定ファイルで割り当てた文字キーを別の操作に割り当てた
+                        if bound.contains(&other) {
+                            return Err(format!("{name}: \"{c}\"は他の操作に割り当て済みです"));
+                        }
+                        taken.push((key.span(), name.clone(), c, other));
+                    }
+                    Ok(())
+                });
+            if let Err(message) = result {
+                error(key.span(), message);
+            }
+        }
+
+        for (key, value) in in_order(sections.syntax) {
+            if let Err(message) = config.set_syntax(key.get_ref(), &value_text(value.get_ref())) {
+                error(key.span(), message);
+            }
+        }
+
+        // 初期設定の文字キーを奪われ、割り当て直されていない操作がある
+        for (span, key, c, other) in taken {
+            if !bound.contains(&other) {
+                error(
+                    span,
+                    format!("{key}: \"{c}\"は他の操作に割り当てられています"),
+                );
+            }
+        }
+        errors.sort_by_key(|error| error.line);
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// 設定ファイルのテーマを`theme`に書き換えて保存する。<br>
+    /// 他の設定やコメントはそのまま残す
+    pub fn save_theme(theme: &str) -> io::Result<()> {
+        let dir = Config::dir().ok_or(io::Error::other("設定フォルダが見つかりません"))?;
+        let path = dir.join(FILE_NAME);
+
+        let text = match read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        fs::create_dir_all(&dir)?;
+        fs::write(path, replace_theme(&text, theme))
+    }
+
+    /// `[general]`の値を設定する
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "tab_width" => self.tab_width = parse_number(key, value, TAB_WIDTH_RANGE)?,
+            "line_numbers" => self.line_numbers = parse_bool(key, value)?,
+            "show_hidden" => self.show_hidden = parse_bool(key, value)?,
+            "respect_ignore" => self.respect_ignore = parse_bool(key, value)?,
+            "list_width" => self.list_width = parse_number(key, value, 10..=90)? as u16,
+            "theme" => {
+                if !syntax::theme_exists(value) {
+                    return Err(format!("テーマ\"{value}\"が見つかりません"));
+                }
+                self.theme = value.to_string();
+            }
+            _ => return Err(format!("不明な設定 {key}")),
+        }
+        Ok(())
+    }
+
+    /// `[keys]`のキーの割り当てを設定する。<br>
+    /// 値は"w,k"のようにカンマ区切りの一文字か、["w", "k"]のような配列<br>
+    /// 割り当てた操作と、他の操作から外した文字キーを返す
+    fn set_key(
+        &mut self,
+        key: &str,
+        value: &toml::Value,
+    ) -> Result<(Key, Vec<(char, Key)>), String> {
+        let action = Key::from_name(key).ok_or(format!("不明な操作 {key}"))?;
+
+        let names: Vec<&str> = match value {
+            toml::Value::String(text) => text.split(',').map(str::trim).collect(),
+            toml::Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .ok_or(format!("{key}: キーは文字列で指定してください"))
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err(format!("{key}: キーは文字列で指定してください")),
+        };
+
+        let mut chars = Vec::new();
+        for c in names {
+            let mut iter = c.chars();
+            match (iter.next(), iter.next()) {
+                (Some(c), None) => chars.push(c),
+                _ => return Err(format!("{key}: キーは一文字で指定してください \"{c}\"")),
+            }
+        }
+
+        Ok((action, self.keymap.bind(action, &chars)))
+    }
+
+    /// `[syntax]`のファイル名のパターンに対するシンタックスを設定する
+    fn set_syntax(&mut self, pattern: &str, value: &str) -> Result<(), String> {
+        let name =
+            syntax::syntax_name(value).ok_or(format!("シンタックス\"{value}\"が見つかりません"))?;
+        self.syntax_map.push((pattern.to_string(), name));
+        Ok(())
+    }
+
+    /// `[syntax]`で`file_name`に指定されたシンタックスを取得する。<br>
+    /// 複数のパターンに一致する場合は先に書かれたもの
+    pub fn syntax_for(&self, file_name: &str) -> Option<&'static str> {
+        self.syntax_map
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, file_name))
+            .map(|&(_, name)| name)
+    }
+}
+
+/// `*`(0文字以上)と`?`(1文字)を使ったパターンに`name`が一致するか
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // 最後に出てきた`*`の位置と、その時の`name`の位置
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            // 一致しなければ`*`に一文字多く含めてやり直す
+            _ => match star {
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp + 1;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 設定ファイルの中身のテーマの値を書き換える。無ければ先頭に追加する<br>
+/// 他の行やコメントはそのまま残す
+fn replace_theme(text: &str, theme: &str) -> String {
+    let value = toml::Value::String(theme.to_string()).to_string();
+
+    let general = toml::from_str::<Sections>(text).map(|sections| sections.general);
+    let span = [toml::from_str::<Entries>(text), general]
+        .into_iter()
+        .filter_map(Result::ok)
+        .find_map(|entries| entries.get("theme").map(Spanned::span));
+
+    match span {
+        Some(span) => format!("{}{value}{}", &text[..span.start], &text[span.end..]),
+        None => format!("theme = {value}\n{text}"),
+    }
+}
+
+/// `entries`を設定ファイルに書かれた順に並べる
+fn in_order(entries: Entries) -> Vec<(Spanned<String>, Spanned<toml::Value>)> {
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort_by_key(|(key, _)| key.span().start);
+    entries
+}
+
+/// 値を文字列にする。文字列は"で囲まない
+fn value_text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// 範囲内の数値に変換する
+pub fn parse_number(
+    key: &str,
+    value: &str,
+    range: std::ops::RangeInclusive<usize>,
+) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if range.contains(&n) => Ok(n),
+        _ => Err(format!(
+            "{key}は{}から{}の数値で指定してください: {value}",
+            range.start(),
+            range.end()
+        )),
+    }
+}
+
+/// true・falseに変換する
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    value
+        .parse::<bool>()
+        .map_err(|_| format!("{key}はtrueかfalseで指定してください: {value}"))
+}
+
+#[test]
+fn parse_test() {
+    use crossterm::event::KeyCode;
+
+    let config = Config::parse(
+        "# コメント\ntab_width = 2 # 行末のコメント\nline_numbers = true\nrespect_ignore = true\n\n[keys]\nup = \"k, w\"\nsearch = \"#\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.tab_width, 2);
+    assert!(config.line_numbers);
+    assert!(config.respect_ignore);
+    assert_eq!(config.keymap.find(KeyCode::Char('k')), Key::Up);
+    assert_eq!(config.keymap.find(KeyCode::Char('w')), Key::Up);
+    assert_eq!(config.keymap.find(KeyCode::Char('#')), Key::Search);
+    assert_eq!(config.keymap.find(KeyCode::Char('/')), Key::None);
+
+    let errors = Config::parse(
+        "tab_width = 20\ntheme = \"none\"\n[keys]\nfoo = \"a\"\nup = \"ab\"\n[other]\n",
+    )
+    .unwrap_err();
+    let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![1, 2, 4, 5, 6]);
+
+    // TOMLとして読めない場合はその行を返す
+    let errors = Config::parse("tab_width = 2\ntheme = none\n").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 2);
+
+    // [general]・配列・エスケープした"も読める
+    let config = Config::parse(
+        "[general]\ntab_width = 8\n[keys]\nup = [\"k\", \",\"]\n[syntax]\n\"a\\\"b\" = \"bash\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.tab_width, 8);
+    assert_eq!(config.keymap.find(KeyCode::Char(',')), Key::Up);
+    assert_eq!(config.syntax_for("a\"b"), Some("Bourne Again Shell (bash)"));
+
+    // 他の操作の文字キーは、その操作も割り当て直した場合だけ使える
+    assert_eq!(Config::parse("[keys]\nup = \"s\"").unwrap_err()[0].line, 2);
+    assert_eq!(
+        Config::parse("[keys]\nup = \"k\"\ndown = \"k\"").unwrap_err()[0].line,
+        3
+    );
+    let config = Config::parse("[keys]\nup = \"s\"\ndown = \"w\"").unwrap();
+    assert_eq!(config.keymap.find(KeyCode::Char('s')), Key::Up);
+    assert_eq!(config.keymap.find(KeyCode::Char('w')), Key::Down);
+
+    let config =
+        Config::parse("[syntax]\n\"*.conf\" = \"bash\"\nJenkinsfile = \"groovy\"\n").unwrap();
+    assert_eq!(
+        config.syntax_for("nginx.conf"),
+        Some("Bourne Again Shell (bash)")
+    );
+    assert_eq!(config.syntax_for("Jenkinsfile"), Some("Groovy"));
+    assert_eq!(config.syntax_for("main.rs"), None);
+    assert!(Config::parse("[syntax]\n\"*.x\" = \"none\"\n").is_err());
+}
+
+#[test]
+fn glob_match_test() {
+    assert!(glob_match("*.conf", "nginx.conf"));
+    assert!(glob_match("Makefile*", "Makefile.am"));
+    assert!(glob_match("a?c*d", "abcxxd"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("*.conf", "nginx.config"));
+    assert!(!glob_match("a?c", "ac"));
+}
+
+#[test]
+fn replace_theme_test() {
+    assert_eq!(replace_theme("", "a"), "theme = \"a\"\n");
+    assert_eq!(
+        replace_theme("tab_width = 2\ntheme = \"b\" # コメント\n[keys]\n", "a"),
+        "tab_width = 2\ntheme = \"a\" # コメント\n[keys]\n"
+    );
+    assert_eq!(
+        replace_theme("[general]\ntheme = \"b\"\n", "a"),
+        "[general]\ntheme = \"a\"\n"
+    );
+    assert_eq!(
+        replace_theme("[keys]\nup = \"k\"\n", "a"),
+        "theme = \"a\"\n[keys]\nup = \"k\"\n"
+    );
+}
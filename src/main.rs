@@ -1,4 +1,6 @@
+mod cli;
 mod components;
+mod config;
 mod ui;
 
 use crate::ui::{
@@ -6,41 +8,143 @@ use crate::ui::{
     events::{self, Key},
     search_dir,
 };
+use crate::{cli::Command, config::Config};
 use crossterm::terminal;
-use std::env;
+use std::{env, process, time::Duration};
 
 fn main() {
-    // CLI 引数の簡易パース: --space N
-    let args: Vec<String> = env::args().collect();
-    let mut space: usize = 4;
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--space" | "-s" => {
-                if i + 1 < args.len() {
-                    if let Ok(v) = args[i + 1].parse::<usize>() {
-                        space = v;
-                    }
-                    i += 1;
-                }
+    // 設定ファイルの読み込み
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err((path, errors)) => {
+            eprintln!("設定ファイルに誤りがあります: {}", path.display());
+            for err in errors {
+                eprintln!("  {err}");
             }
-            _ => {}
+            process::exit(1);
         }
-        i += 1;
-    }
+    };
+
+    // CLI 引数の解析
+    let start = match cli::parse(env::args().skip(1), &mut config) {
+        Ok(Command::Run(path)) => path,
+        Ok(Command::Help) => {
+            println!("{}", cli::HELP);
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("{}", cli::version());
+            return;
+        }
+        Err(err) => {
+            eprintln!("エラー: {err}");
+            eprintln!("使い方は tui-cat --help を確認してください");
+            process::exit(2);
+        }
+    };
 
     //初期設定
-    let mut list = search_dir::Events::new(space);
+    let start = start.unwrap_or_else(|| env::current_dir().unwrap());
+    // 一覧に表示するフォルダを読めなければ終了する
+    let dir = if start.is_dir() {
+        start.as_path()
+    } else {
+        start.parent().unwrap()
+    };
+    if let Err(err) = dir.read_dir() {
+        eprintln!("エラー: {}を開けません: {err}", dir.display());
+        process::exit(2);
+    }
+
+    let mut list = if start.is_dir() {
+        let mut list = search_dir::Events::new(config, start);
+        list.next();
+        list
+    } else {
+        // ファイルが指定された場合はそのファイルを開く
+        let dir = start.parent().unwrap().to_path_buf();
+        let mut list = search_dir::Events::new(config, dir);
+        list.open_path(start);
+        list
+    };
 
-    list.next();
     //rawモードon
     terminal::enable_raw_mode().unwrap();
 
     loop {
+        let grep_running = list.grep.poll();
+        let search_running = list.poll_search();
+        list.load_visible_lines();
+        list.apply_highlight();
         draw(&mut list);
 
+        // ハイライト中・検索中はキー入力が無くても描画し直す
+        let busy = list.highlight.is_some() || grep_running || search_running;
+        if busy && !events::wait(Duration::from_millis(50)) {
+            continue;
+        }
+
+        // 検索文字列の入力中
+        if list.search.input_mode {
+            list.search_input(events::input_text());
+            continue;
+        }
+
+        // 絞り込み文字列の入力中
+        if list.filter.input_mode {
+            list.filter_input(events::input_text());
+            continue;
+        }
+
+        // grep文字列の入力中
+        if list.grep.input_mode {
+            list.grep_input(events::input_text());
+            continue;
+        }
+
+        // 文字コードの入力中
+        if list.encoding_input.is_some() {
+            list.encoding_input(events::input_text());
+            continue;
+        }
+
+        // シンタックスの入力中
+        if list.syntax_input.is_some() {
+            list.syntax_input(events::input_text());
+            continue;
+        }
+
         //入力
-        list.key = events::input();
+        list.key = events::input(&list.config.keymap);
+
+        // grepの検索結果表示中の処理
+        if list.grep.active {
+            match list.key {
+                Key::Exit | Key::ExitMove => break,
+                Key::Up => list.grep.back(),
+                Key::Down => list.grep.next(),
+                Key::Enter | Key::Next => list.open_grep_result(),
+                Key::Back => list.grep.close(),
+                Key::Grep => list.start_grep(),
+                _ => {}
+            }
+
+            continue;
+        }
+
+        // Gitの履歴表示中の処理
+        if list.log.active {
+            match list.key {
+                Key::Exit | Key::ExitMove => break,
+                Key::Up => list.log.back(),
+                Key::Down => list.log.next(),
+                Key::Enter | Key::Next => list.open_log_commit(),
+                Key::Back | Key::Log => list.log.close(),
+                _ => {}
+            }
+
+            continue;
+        }
 
         // サブモード時の処理
         if list.submode {
@@ -57,7 +161,7 @@ fn main() {
                     }
                 }
                 Key::Change => list.change(),
-                Key::Enter => {}
+                Key::Enter => list.toggle_node(),
                 Key::Next => {
                     if !list.property_mode {
                         list.subnext()
@@ -71,6 +175,33 @@ fn main() {
                 Key::LineMode => list.change_linemode(),
                 Key::MoveFirstLine => list.move_first_line(),
                 Key::MoveLastLine => list.move_last_line(),
+                Key::Search => list.start_search(),
+                Key::SearchNext => list.search_next(),
+                Key::SearchPrev => list.search_prev(),
+                Key::Grep => list.start_grep(),
+                Key::HexMode => list.change_hex_mode(),
+                Key::Encoding => list.start_encoding(),
+                Key::Theme => list.change_theme(),
+                Key::Syntax => list.start_syntax(),
+                Key::Markdown => list.change_markdown_mode(),
+                Key::Tree => list.change_tree_mode(),
+                Key::Table => list.change_table_mode(),
+                Key::Sort => list.sort_table(),
+                Key::Wrap => list.change_wrap_mode(),
+                Key::Mark => list.mark_file(),
+                Key::Diff => list.change_diff_mode(),
+                Key::NextHunk => list.next_hunk(),
+                Key::PrevHunk => list.prev_hunk(),
+                Key::ChangedOnly => list.change_changed_only(),
+                Key::Blame => list.change_blame_mode(),
+                Key::Log => list.start_log(),
+                Key::Hidden => list.change_hidden(),
+                Key::Ignore => list.change_ignore(),
+                Key::ListSort => list.change_list_sort(),
+                Key::ListReverse => list.reverse_list_sort(),
+                Key::Detail => list.change_detail(),
+                Key::JumpLink => list.jump_link(),
+                Key::Refresh => list.refresh(),
                 Key::None => {}
             }
 
@@ -93,6 +224,32 @@ fn main() {
             Key::LineMode => list.change_linemode(),
             Key::MoveFirstLine => list.move_first_line(),
             Key::MoveLastLine => list.move_last_line(),
+            Key::Search => list.start_filter(),
+            Key::SearchNext | Key::SearchPrev => {}
+            Key::Grep => list.start_grep(),
+            Key::HexMode => list.change_hex_mode(),
+            Key::Encoding => list.start_encoding(),
+            Key::Theme => list.change_theme(),
+            Key::Syntax => list.start_syntax(),
+            Key::Markdown => list.change_markdown_mode(),
+            Key::Tree => list.change_tree_mode(),
+            Key::Table => list.change_table_mode(),
+            Key::Sort => list.sort_table(),
+            Key::Wrap => list.change_wrap_mode(),
+            Key::Mark => list.mark_file(),
+            Key::Diff => list.change_diff_mode(),
+            Key::NextHunk => list.next_hunk(),
+            Key::PrevHunk => list.prev_hunk(),
+            Key::ChangedOnly => list.change_changed_only(),
+            Key::Blame => list.change_blame_mode(),
+            Key::Log => list.start_log(),
+            Key::Hidden => list.change_hidden(),
+            Key::Ignore => list.change_ignore(),
+            Key::ListSort => list.change_list_sort(),
+            Key::ListReverse => list.reverse_list_sort(),
+            Key::Detail => list.change_detail(),
+            Key::JumpLink => list.jump_link(),
+            Key::Refresh => list.refresh(),
             Key::None => {}
         }
     }
@@ -12,15 +12,19 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::Line,
-    widgets::{self, Block, Borders, List, ListItem, Paragraph, Tabs},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{
+        self, Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, TableState, Tabs,
+    },
     Terminal,
 };
 
 use console::Emoji;
 
+use crate::components::git::GitLog;
 use crate::search_dir::Events;
+use crate::ui::syntax;
 
 ///描写
 #[inline]
@@ -30,11 +34,20 @@ pub fn draw(events: &mut Events) {
 
     terminal
         .draw(|f| {
-            let tab = thread::spawn(|| {
-                let titles: Vec<Line> = ["終了q", "開く Enter,→", "戻る ←", "選択 ↑↓,ws"]
-                    .into_iter()
-                    .map(Line::from)
-                    .collect();
+            let sort = events.sort.label() + " O,R";
+            let tab = thread::spawn(move || {
+                let titles: Vec<Line> = [
+                    "終了q",
+                    "開く Enter,→",
+                    "戻る ←",
+                    "選択 ↑↓,ws",
+                    "検索・絞り込み /",
+                    "grep f",
+                ]
+                .into_iter()
+                .map(Line::from)
+                .chain([Line::from(sort)])
+                .collect();
 
                 Tabs::new(titles)
                     .block(Block::default().borders(Borders::ALL))
@@ -48,7 +61,13 @@ pub fn draw(events: &mut Events) {
 
             let main_display = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+                .constraints(
+                    [
+                        Constraint::Percentage(events.list_width()),
+                        Constraint::Percentage(100 - events.list_width()),
+                    ]
+                    .as_ref(),
+                )
                 .split(chunks[0]);
 
             let items = events
@@ -56,52 +75,289 @@ pub fn draw(events: &mut Events) {
                 .iter()
                 .enumerate()
                 .map(|(num, i)| {
-                    if num == 0 {
-                        i.iter()
-                            .map(|s| {
-                                ListItem::new(Emoji("📂 ", "").to_string() + s.to_str().unwrap())
-                            })
-                            .collect::<Vec<ListItem>>()
+                    let icon = if num == 0 {
+                        Emoji("📂 ", "")
                     } else {
-                        i.iter()
-                            .map(|s| {
-                                ListItem::new(Emoji("📃 ", "").to_string() + s.to_str().unwrap())
-                            })
-                            .collect::<Vec<ListItem>>()
-                    }
+                        Emoji("📃 ", "")
+                    };
+
+                    i.iter()
+                        .enumerate()
+                        .map(|(index, s)| {
+                            let name = s.to_str().unwrap();
+                            let link = events.links.get(s);
+                            // 差分の比較元に印を付けたファイル
+                            let icon = if events.is_marked(s) {
+                                Emoji("📌 ", "* ").to_string()
+                            } else if link.is_some() {
+                                Emoji("🔗 ", "@ ").to_string()
+                            } else {
+                                icon.to_string()
+                            };
+                            let mut spans = Vec::new();
+                            // Gitの状態を名前の前に表示し、名前に色を付ける
+                            let style = match (&events.git, events.git_status(s)) {
+                                (Some(_), Some(status)) => {
+                                    let style = Style::default().fg(status.color());
+                                    spans.push(Span::styled(status.mark(), style));
+                                    style
+                                }
+                                (Some(_), None) => {
+                                    spans.push(Span::raw("  "));
+                                    Style::default()
+                                }
+                                (None, _) => Style::default(),
+                            };
+                            // シンボリックリンクは色を変え、名前の後にリンク先を表示する
+                            let style = match link {
+                                Some(link) => style.fg(link.color()),
+                                None => style,
+                            };
+                            let target = link.map(|link| {
+                                Span::styled(
+                                    " ".to_string() + &link.label(),
+                                    Style::default().fg(Color::DarkGray),
+                                )
+                            });
+                            spans.push(Span::raw(icon));
+
+                            if !events.filter.is_active() {
+                                spans.push(Span::styled(name, style));
+                                spans.extend(target);
+                                return Line::from(spans);
+                            }
+
+                            // 絞り込みで一致した文字をハイライトする
+                            let positions = &events.filter.positions[num][index];
+                            spans.extend(name.chars().enumerate().map(|(n, c)| {
+                                if positions.contains(&n) {
+                                    Span::styled(c.to_string(), Style::default().fg(Color::Yellow))
+                                } else {
+                                    Span::styled(c.to_string(), style)
+                                }
+                            }));
+                            spans.extend(target);
+                            Line::from(spans)
+                        })
+                        .collect::<Vec<Line>>()
                 })
-                .collect::<Vec<Vec<ListItem>>>()
+                .collect::<Vec<Vec<Line>>>()
                 .concat();
 
-            let item = List::new(items)
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default().fg(Color::White))
-                .highlight_style(Style::default().fg(Color::Green))
-                .highlight_symbol(">>");
+            let list_block = Block::default().borders(Borders::ALL).title({
+                let mut title = Vec::new();
+                if events.changed_only && events.git.is_some() {
+                    title.push("変更のみ".to_string());
+                }
+                if events.filter.is_active() {
+                    title.push("絞り込み: ".to_string() + &events.filter.query);
+                }
+                title.join(" ")
+            });
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(if events.property_mode {
+                    String::new()
+                } else {
+                    events.title()
+                })
+                .border_style(Style::default().fg(if events.submode {
+                    Color::Green
+                } else {
+                    Color::White
+                }));
+
+            // 表示する行のみを取り出す
+            let height = main_display[1].height as usize;
+            let top = events.substate.0.saturating_sub(events.window_start);
+            let visible = events.data.lines.iter().enumerate().skip(top).take(height);
 
             let text = Paragraph::new({
                 if events.property_mode {
                     events.property.as_ref().unwrap().to_text()
                 } else {
-                    events.data.clone()
+                    let lines = visible.map(|(num, line)| {
+                        if events.search.matches.is_empty() {
+                            line.clone()
+                        } else {
+                            // 検索の一致箇所をハイライトする
+                            let skip = events.prefix_spans();
+                            events
+                                .search
+                                .highlight_line(events.window_start + num, line, skip)
+                        }
+                    });
+
+                    if events.is_wrapping() {
+                        // 折り返した行で表示欄を埋める
+                        Text::from(
+                            lines
+                                .flat_map(|line| events.wrap_line(line))
+                                .skip(events.wrap_row)
+                                .take(height)
+                                .collect::<Vec<Line>>(),
+                        )
+                    } else {
+                        Text::from(lines.collect::<Vec<Line>>())
+                    }
                 }
             })
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(if events.submode {
-                        Color::Green
-                    } else {
-                        Color::White
-                    })),
+            .block(block.clone())
+            .style(
+                if events.property_mode || events.hex_mode || !events.path.is_file() {
+                    Style::default().fg(Color::White)
+                } else {
+                    // テーマの背景色で表示部分全体を塗る
+                    syntax::theme_style(&events.config.theme)
+                },
             )
-            .style(Style::default().fg(Color::White))
-            .scroll(events.substate);
+            .scroll(if events.property_mode {
+                (events.substate.0 as u16, events.substate.1)
+            } else if events.is_wrapping() {
+                (0, 0)
+            } else {
+                (0, events.substate.1)
+            });
 
             f.render_widget(widgets::Clear, f.area());
-            f.render_widget(tab.join().unwrap(), chunks[1]);
-            f.render_stateful_widget(item, main_display[0], &mut events.state);
-            f.render_widget(text, main_display[1]);
+            let tab = tab.join().unwrap();
+            match events.prompt() {
+                // 文字列の入力欄
+                Some(prompt) => {
+                    let prompt = Paragraph::new(prompt)
+                        .block(Block::default().borders(Borders::ALL))
+                        .style(Style::default().fg(Color::White));
+                    f.render_widget(prompt, chunks[1]);
+                }
+                None => f.render_widget(tab, chunks[1]),
+            }
+            if events.detail {
+                // 大きさ・更新日時・権限・所有者を並べた表
+                let rows = events
+                    .items
+                    .concat()
+                    .iter()
+                    .zip(items)
+                    .map(|(name, line)| {
+                        let mut cells = vec![Cell::from(line)];
+                        if let Some(property) = events.details.get(name) {
+                            cells.extend([
+                                Cell::from(Line::from(property.human_size()).right_aligned()),
+                                Cell::from(property.relative_update()),
+                                Cell::from(property.permissions()),
+                                Cell::from(property.owner()),
+                            ]);
+                        }
+                        Row::new(cells)
+                    })
+                    .collect::<Vec<Row>>();
+                let widths = [
+                    Constraint::Min(10),
+                    Constraint::Length(5),
+                    Constraint::Length(8),
+                    Constraint::Length(10),
+                    Constraint::Length(8),
+                ];
+
+                let table = Table::new(rows, widths)
+                    .block(list_block)
+                    .style(Style::default().fg(Color::White))
+                    .highlight_style(Style::default().fg(Color::Green))
+                    .highlight_symbol(">>");
+                // 一覧の選択位置・スクロール位置を表と共有する
+                let mut state = TableState::default()
+                    .with_offset(events.state.offset())
+                    .with_selected(events.state.selected());
+                f.render_stateful_widget(table, main_display[0], &mut state);
+                *events.state.offset_mut() = state.offset();
+            } else {
+                let item = List::new(items)
+                    .block(list_block)
+                    .style(Style::default().fg(Color::White))
+                    .highlight_style(Style::default().fg(Color::Green))
+                    .highlight_symbol(">>");
+                f.render_stateful_widget(item, main_display[0], &mut events.state);
+            }
+
+            if events.grep.active {
+                // grepの検索結果
+                let results = events
+                    .grep
+                    .results
+                    .iter()
+                    .map(|result| ListItem::new(events.grep.format(result)))
+                    .collect::<Vec<ListItem>>();
+
+                let results = List::new(results)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(events.grep.title())
+                            .border_style(Style::default().fg(Color::Green)),
+                    )
+                    .style(Style::default().fg(Color::White))
+                    .highlight_style(Style::default().fg(Color::Green))
+                    .highlight_symbol(">>");
+
+                f.render_stateful_widget(results, main_display[1], &mut events.grep.state);
+            } else if events.log.active {
+                // 開いているファイルの履歴
+                let commits = events
+                    .log
+                    .commits
+                    .iter()
+                    .map(|commit| ListItem::new(GitLog::format(commit)))
+                    .collect::<Vec<ListItem>>();
+
+                let commits = List::new(commits)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!("履歴 ({}件)", events.log.commits.len()))
+                            .border_style(Style::default().fg(Color::Green)),
+                    )
+                    .style(Style::default().fg(Color::White))
+                    .highlight_style(Style::default().fg(Color::Green))
+                    .highlight_symbol(">>");
+
+                f.render_stateful_widget(commits, main_display[1], &mut events.log.state);
+            } else if let Some(table) = events.table.as_ref().filter(|_| !events.property_mode) {
+                // CSV・TSVの表。見出しは常に表示し、substate.1の列から表示する
+                let first = events.substate.1 as usize;
+                let cells = |row: &[String]| {
+                    row.iter()
+                        .skip(first)
+                        .map(|cell| Cell::from(cell.clone()))
+                        .collect::<Vec<Cell>>()
+                };
+
+                let header = Row::new(cells(&table.header)).style(
+                    Style::default()
+                        .fg(Color::LightCyan)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                );
+                let rows = table
+                    .rows()
+                    .skip(events.substate.0)
+                    .take(height)
+                    .map(|row| Row::new(cells(row)))
+                    .collect::<Vec<Row>>();
+                let widths = table
+                    .widths
+                    .iter()
+                    .skip(first)
+                    .map(|&width| Constraint::Length(width));
+
+                let table = Table::new(rows, widths)
+                    .header(header)
+                    .column_spacing(2)
+                    .block(block)
+                    .style(Style::default().fg(Color::White));
+                f.render_widget(table, main_display[1]);
+            } else {
+                f.render_widget(text, main_display[1]);
+            }
         })
         .unwrap();
 }
@@ -1,6 +1,7 @@
-use crossterm::event::{read, Event, KeyCode, KeyEventKind};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind};
+use std::{collections::HashMap, time::Duration};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Key {
     ///上　↑もしくはw
     Up,
@@ -26,58 +27,257 @@ pub enum Key {
     MoveLastLine,
     /// 先頭に移動する
     MoveFirstLine,
+    /// ファイル内検索 /
+    Search,
+    /// 次の検索結果に移動する n
+    SearchNext,
+    /// 前の検索結果に移動する N
+    SearchPrev,
+    /// フォルダ以下のファイルの中身を検索 f
+    Grep,
+    /// 16進数表示切り替え x
+    HexMode,
+    /// 文字コードを指定して読み込み直す c
+    Encoding,
+    /// テーマ切り替え t
+    Theme,
+    /// シンタックスを指定してハイライトし直す y
+    Syntax,
+    /// Markdownの表示切り替え m
+    Markdown,
+    /// JSON・YAML・TOMLの木構造表示切り替え v
+    Tree,
+    /// CSV・TSVの表表示切り替え T
+    Table,
+    /// 表の左端の列で並べ替える o
+    Sort,
+    /// 折り返し表示の切り替え r
+    Wrap,
+    /// 差分の比較元に印を付ける M
+    Mark,
+    /// 印を付けたファイルとの差分表示切り替え D
+    Diff,
+    /// 次の変更箇所に移動する ]
+    NextHunk,
+    /// 前の変更箇所に移動する [
+    PrevHunk,
+    /// Gitで変更のあるファイルだけを表示する S
+    ChangedOnly,
+    /// 各行を最後に変更したコミットの表示切り替え b
+    Blame,
+    /// 開いているファイルの履歴 L
+    Log,
+    /// 隠しファイルの表示切り替え h
+    Hidden,
+    /// .gitignore・.ignoreで無視されるものの表示切り替え i
+    Ignore,
+    /// ファイル一覧の並べる基準の切り替え O
+    ListSort,
+    /// ファイル一覧を逆順にする R
+    ListReverse,
+    /// ファイル一覧の大きさ・更新日時などの表示切り替え I
+    Detail,
+    /// シンボリックリンクのリンク先に移動する J
+    JumpLink,
+    /// ファイル一覧とGitでの状態を読み込み直す u
+    Refresh,
     ///例外
     None,
 }
 
+/// 文字キーの初期割り当て
+const DEFAULT_KEYS: [(char, Key); 38] = [
+    ('a', Key::Back),
+    ('d', Key::Next),
+    ('q', Key::Exit),
+    ('Q', Key::ExitMove),
+    ('w', Key::Up),
+    ('s', Key::Down),
+    ('e', Key::Change),
+    ('p', Key::PropertyMode),
+    ('l', Key::LineMode),
+    ('g', Key::MoveFirstLine),
+    ('G', Key::MoveLastLine),
+    ('/', Key::Search),
+    ('n', Key::SearchNext),
+    ('N', Key::SearchPrev),
+    ('f', Key::Grep),
+    ('x', Key::HexMode),
+    ('c', Key::Encoding),
+    ('t', Key::Theme),
+    ('y', Key::Syntax),
+    ('m', Key::Markdown),
+    ('v', Key::Tree),
+    ('T', Key::Table),
+    ('o', Key::Sort),
+    ('r', Key::Wrap),
+    ('M', Key::Mark),
+    ('D', Key::Diff),
+    (']', Key::NextHunk),
+    ('[', Key::PrevHunk),
+    ('S', Key::ChangedOnly),
+    ('b', Key::Blame),
+    ('L', Key::Log),
+    ('h', Key::Hidden),
+    ('i', Key::Ignore),
+    ('O', Key::ListSort),
+    ('R', Key::ListReverse),
+    ('I', Key::Detail),
+    ('J', Key::JumpLink),
+    ('u', Key::Refresh),
+];
+
 impl Key {
-    pub const fn find(f: KeyCode) -> Key {
+    /// 設定ファイルで使う名前から[Key]を取得する
+    pub fn from_name(name: &str) -> Option<Key> {
+        Some(match name {
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "next" => Key::Next,
+            "back" => Key::Back,
+            "change" => Key::Change,
+            "exit" => Key::Exit,
+            "exit_move" => Key::ExitMove,
+            "property" => Key::PropertyMode,
+            "line_numbers" => Key::LineMode,
+            "first_line" => Key::MoveFirstLine,
+            "last_line" => Key::MoveLastLine,
+            "search" => Key::Search,
+            "search_next" => Key::SearchNext,
+            "search_prev" => Key::SearchPrev,
+            "grep" => Key::Grep,
+            "hex" => Key::HexMode,
+            "encoding" => Key::Encoding,
+            "theme" => Key::Theme,
+            "syntax" => Key::Syntax,
+            "markdown" => Key::Markdown,
+            "tree" => Key::Tree,
+            "table" => Key::Table,
+            "sort" => Key::Sort,
+            "wrap" => Key::Wrap,
+            "mark" => Key::Mark,
+            "diff" => Key::Diff,
+            "next_hunk" => Key::NextHunk,
+            "prev_hunk" => Key::PrevHunk,
+            "changed_only" => Key::ChangedOnly,
+            "blame" => Key::Blame,
+            "log" => Key::Log,
+            "hidden" => Key::Hidden,
+            "ignore" => Key::Ignore,
+            "list_sort" => Key::ListSort,
+            "list_reverse" => Key::ListReverse,
+            "details" => Key::Detail,
+            "jump_link" => Key::JumpLink,
+            "refresh" => Key::Refresh,
+            _ => return None,
+        })
+    }
+}
+
+/// 文字キーと[Key]の割り当て
+#[derive(Debug, Clone)]
+pub struct Keymap(HashMap<char, Key>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap(DEFAULT_KEYS.into_iter().collect())
+    }
+}
+
+impl Keymap {
+    /// 入力されたキーから[Key]を取得する
+    pub fn find(&self, f: KeyCode) -> Key {
         match f {
             KeyCode::Up => Key::Up,
             KeyCode::Down => Key::Down,
             KeyCode::Right => Key::Next,
             KeyCode::Enter => Key::Enter,
             KeyCode::Left => Key::Back,
-            KeyCode::Char(c) => match c {
-                'a' => Key::Back,
-                'd' => Key::Next,
-                'q' => Key::Exit,
-                'Q' => Key::ExitMove,
-                'w' => Key::Up,
-                's' => Key::Down,
-                'e' => Key::Change,
-                'p' => Key::PropertyMode,
-                'l' => Key::LineMode,
-                'g' => Key::MoveFirstLine,
-                'G' => Key::MoveLastLine,
-                _ => Key::None,
-            },
+            KeyCode::Char(c) => self.0.get(&c).copied().unwrap_or(Key::None),
             _ => Key::None,
         }
     }
+
+    /// `key`の文字キーを`chars`に割り当て直す。<br>
+    /// 他の操作から外した文字キーがあれば、その文字と操作を返す
+    pub fn bind(&mut self, key: Key, chars: &[char]) -> Vec<(char, Key)> {
+        self.0.retain(|_, k| *k != key);
+
+        let mut taken = Vec::new();
+        for &c in chars {
+            if let Some(other) = self.0.insert(c, key).filter(|&other| other != key) {
+                taken.push((c, other));
+            }
+        }
+        taken
+    }
+}
+
+/// 文字列入力欄での入力
+#[derive(Debug)]
+pub enum TextInput {
+    /// 入力された文字
+    Char(char),
+    /// 一文字削除 BackSpace
+    Backspace,
+    /// 確定 Enter
+    Enter,
+    /// 取り消し Esc
+    Cancel,
+    /// 入力方法の切り替え Tab
+    Toggle,
+    ///例外
+    None,
 }
 
 /// キー入力から値を取得する。
+///
+/// 一文字づつ値を読み込み[Key]を返す
 /// # Exsample
 /// ``` rust
 /// use crate::ui::events;
-/// let key: Key = event::input();
+/// let key: Key = event::input(&Keymap::default());
 ///
 /// // wキーを押す
 /// print!("{:?}", key);
 /// // Key::Up
 /// ```
 #[inline]
-pub fn input() -> Key {
-    //! 標準入力
-    //!
-    //! 一文字づつ値を読み込み[Key]を返す
+pub fn input(keymap: &Keymap) -> Key {
     if let Event::Key(f) = read().unwrap() {
         match f.kind {
-            KeyEventKind::Press | KeyEventKind::Repeat => Key::find(f.code),
+            KeyEventKind::Press | KeyEventKind::Repeat => keymap.find(f.code),
             _ => Key::None,
         }
     } else {
         Key::None
     }
 }
+
+/// `timeout`の間キー入力を待つ。入力があれば`true`
+#[inline]
+pub fn wait(timeout: Duration) -> bool {
+    poll(timeout).unwrap_or(false)
+}
+
+/// 文字列入力欄のキー入力から値を取得する。
+///
+/// [input]と違い文字をそのまま[TextInput::Char]として返す
+#[inline]
+pub fn input_text() -> TextInput {
+    if let Event::Key(f) = read().unwrap() {
+        match f.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => match f.code {
+                KeyCode::Char(c) => TextInput::Char(c),
+                KeyCode::Backspace => TextInput::Backspace,
+                KeyCode::Enter => TextInput::Enter,
+                KeyCode::Esc => TextInput::Cancel,
+                KeyCode::Tab => TextInput::Toggle,
+                _ => TextInput::None,
+            },
+            _ => TextInput::None,
+        }
+    } else {
+        TextInput::None
+    }
+}
@@ -1,5 +1,4 @@
 pub mod draw;
 pub mod events;
 pub mod search_dir;
-
-mod syntax;
+pub mod syntax;
@@ -1,19 +1,49 @@
-use crate::components::check_property::Property;
+use crate::components::{
+    check_property::Property,
+    diff::{Diff, DiffMode},
+    encoding,
+    filter::Filter,
+    git::{self as git_cmd, Commit, GitLog, GitStatus, Status},
+    grep::Grep,
+    hex,
+    lazy_file::LazyFile,
+    markdown,
+    search::Search,
+    sort::ListSort,
+    symlink::Symlink,
+    table::{self, CsvTable},
+    tree::{Format, Tree},
+    wrap::{self, WrapMode},
+};
 
 use crossterm::terminal;
+use encoding_rs::{Encoding, UTF_8};
+use ignore::WalkBuilder;
 use ratatui::{
-    text::{Span, Text},
+    style::{Color, Style},
+    text::{Line, Span, Text},
     widgets::ListState,
 };
 use std::{
-    env,
+    collections::{HashMap, HashSet},
     ffi::{OsStr, OsString},
-    fs::{read_to_string, ReadDir},
-    path::PathBuf,
+    fs::{self, ReadDir},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::ui::{
+    events::TextInput,
+    syntax::{self, BackgroundHighlight},
 };
+use crate::{config::Config, Key};
 
-use crate::ui::syntax;
-use crate::Key;
+/// これより大きいファイル(バイト)は表示する行だけを読み込む
+const LAZY_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// ファイル一覧を表で表示する際の一覧の最小の幅(%)
+const DETAIL_LIST_WIDTH: u16 = 50;
+/// 大きいファイルを開いた際に表示する行の前後に読み込んでおく行数
+const WINDOW_MARGIN: usize = 500;
 
 pub struct Events<'a> {
     ///ディレクトリ内のファイル・フォルダを取得
@@ -28,40 +58,134 @@ pub struct Events<'a> {
     /// - false <br>サブモードoff
     pub submode: bool,
     ///サブ時の値保存場所
-    pub substate: (u16, u16),
+    /// (先頭に表示する行, 横方向のスクロール)
+    pub substate: (usize, u16),
     ///キーボードで入力された文字列
     pub key: Key,
     ///ファイルの中身
+    /// [Events::lazy]が[Some]の場合は[Events::window_start]行目からの一部分のみ
     pub data: Text<'a>,
+    /// 大きなファイルを開いた場合に必要な行だけを読み込む
+    pub lazy: Option<LazyFile>,
+    /// バックグラウンドで行っているハイライト
+    pub highlight: Option<BackgroundHighlight>,
+    /// [Events::data]の先頭がファイルの何行目か
+    pub window_start: usize,
+    /// 開いているファイルの大きさ(バイト)
+    pub file_size: u64,
+    /// 16進数表示モード
+    pub hex_mode: bool,
+    /// Markdownを装飾して表示するモード
+    pub markdown_mode: bool,
+    /// Markdownを折り返した幅
+    pub markdown_width: usize,
+    /// JSON・YAML・TOMLの木構造表示
+    pub tree: Option<Tree>,
+    /// CSV・TSVの表表示。[Events::substate]は(先頭に表示する行, 左端に表示する列)になる
+    pub table: Option<CsvTable>,
+    /// 折り返し表示の方法
+    pub wrap: WrapMode,
+    /// 折り返し中に、先頭の行を折り返した何行目から表示するか
+    pub wrap_row: usize,
+    /// 差分の比較元として印を付けたファイル
+    pub marked: Option<PathBuf>,
+    /// 印を付けたファイルとの差分を表示している場合はその情報
+    pub diff: Option<Diff>,
+    /// 一覧のフォルダがGitの作業ツリーの中ならファイルの状態
+    pub git: Option<GitStatus>,
+    /// Gitで変更のあるファイルだけを一覧に表示するかどうか
+    pub changed_only: bool,
+    /// ファイル一覧の並べ方
+    pub sort: ListSort,
+    /// ファイル一覧を大きさ・更新日時などの表で表示するかどうか
+    pub detail: bool,
+    /// 表で表示する際の一覧の各ファイル・フォルダのプロパティ
+    pub details: HashMap<OsString, Property>,
+    /// 一覧にあるシンボリックリンク
+    pub links: HashMap<OsString, Symlink>,
+    /// 各行を最後に変更したコミットを表示するかどうか
+    pub blame: bool,
+    /// 履歴から選んだコミットでのファイルを表示している場合はそのコミット
+    pub revision: Option<Commit>,
+    /// 開いているファイルの履歴
+    pub log: GitLog,
+    /// 開いているファイルの文字コード
+    pub encoding: &'static Encoding,
+    /// 文字コードの指定入力中の文字列
+    pub encoding_input: Option<String>,
+    /// ハイライトに使うシンタックスの名前。無ければ[None]
+    pub syntax: Option<&'static str>,
+    /// シンタックスの指定入力中の文字列
+    pub syntax_input: Option<String>,
+    /// 画面に表示するメッセージ
+    pub message: Option<String>,
     /// プロパティ
     pub property: Option<Property>,
     /// プロパティモード
     pub property_mode: bool,
     /// 行数を表すモード
     pub line_mode: bool,
-    /// タブを何スペースに展開するか
-    pub tab_width: usize,
+    /// 設定
+    pub config: Config,
+    /// ファイル内検索
+    pub search: Search,
+    /// ファイル一覧の絞り込み
+    pub filter: Filter,
+    /// フォルダ以下のファイルの中身の検索
+    pub grep: Grep,
 }
 
 impl<'a> Events<'a> {
-    pub fn new(tab_width: usize) -> Events<'a> {
-        //カウントディレクトリを取得
-        let dir = env::current_dir().unwrap().read_dir().unwrap();
+    /// `path`のフォルダを開いた状態で初期化する
+    pub fn new(config: Config, path: PathBuf) -> Events<'a> {
+        let dir = path.read_dir().unwrap();
 
         let mut eve = Events {
-            items: search_directory(dir),
-            path: env::current_dir().unwrap(),
+            items: Default::default(),
+            path,
             state: ListState::default(),
             submode: false,
             substate: (0, 0),
             key: Key::None,
             data: Text::raw(""),
+            lazy: None,
+            highlight: None,
+            window_start: 0,
+            file_size: 0,
+            hex_mode: false,
+            markdown_mode: false,
+            markdown_width: 0,
+            tree: None,
+            table: None,
+            wrap: WrapMode::default(),
+            wrap_row: 0,
+            marked: None,
+            diff: None,
+            git: None,
+            changed_only: false,
+            sort: ListSort::default(),
+            detail: false,
+            details: HashMap::new(),
+            links: HashMap::new(),
+            blame: false,
+            revision: None,
+            log: GitLog::default(),
+            encoding: UTF_8,
+            encoding_input: None,
+            syntax: None,
+            syntax_input: None,
+            // 追加のシンタックス定義が読み込めなかった場合は知らせる
+            message: syntax::Highlighter::get().syntax_error.clone(),
             property: None,
             property_mode: false,
             line_mode: false,
-            tab_width,
+            config,
+            search: Search::default(),
+            filter: Filter::default(),
+            grep: Grep::default(),
         };
 
+        eve.set_items(dir);
         eve.property = Property::new(eve.path.as_path());
         eve
     }
@@ -69,14 +193,21 @@ impl<'a> Events<'a> {
     ///選択を一つ次に進める
     pub fn next(&mut self) {
         match self.submode {
+            true if self.tree.is_some() => {
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.down();
+                }
+                self.show_tree();
+            }
+            true if self.is_wrapping() => self.scroll_down_wrapped(),
             true => {
                 // submode時の下限サイズ設定
-                if (self.substate.0 as usize) < self.limit_down_size() {
+                if self.substate.0 < self.limit_down_size() {
                     self.substate.0 = self.substate.0.saturating_add(1);
                 }
             }
             false => {
-                if !self.items.is_empty() {
+                if !self.items.concat().is_empty() {
                     let i = match self.state.selected() {
                         Some(i) => {
                             if i >= self.items.concat().len() - 1 {
@@ -97,11 +228,18 @@ impl<'a> Events<'a> {
     #[inline]
     pub fn back(&mut self) {
         match self.submode {
+            true if self.tree.is_some() => {
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.up();
+                }
+                self.show_tree();
+            }
+            true if self.is_wrapping() => self.scroll_up_wrapped(),
             true => {
                 self.substate.0 = self.substate.0.saturating_sub(1);
             }
             false => {
-                if !self.items.is_empty() {
+                if !self.items.concat().is_empty() {
                     let i = match self.state.selected() {
                         Some(i) => {
                             if i == 0 {
@@ -118,16 +256,44 @@ impl<'a> Events<'a> {
         }
     }
 
-    /// substateの値を1増やす
+    /// substateの値を1増やす。<br>
+    /// 木構造表示の場合はカーソルのノードを開く
     #[inline]
     pub fn subnext(&mut self) {
-        self.substate.1 = self.substate.1.saturating_add(1);
+        match self.tree.as_mut() {
+            Some(tree) => {
+                tree.expand();
+                self.show_tree();
+            }
+            // 表表示の場合は最後の列まで
+            None => match &self.table {
+                Some(table) if self.substate.1 as usize + 1 >= table.columns() => {}
+                // 折り返し中は横にスクロールしない
+                None if self.is_wrapping() => {}
+                _ => self.substate.1 = self.substate.1.saturating_add(1),
+            },
+        }
     }
 
-    /// substateの値を1減らす
+    /// substateの値を1減らす。<br>
+    /// 木構造表示の場合はカーソルのノードを閉じる
     #[inline]
     pub fn subback(&mut self) {
-        self.substate.1 = self.substate.1.saturating_sub(1);
+        match self.tree.as_mut() {
+            Some(tree) => {
+                tree.collapse();
+                self.show_tree();
+            }
+            None => self.substate.1 = self.substate.1.saturating_sub(1),
+        }
+    }
+
+    /// 木構造表示の場合はカーソルのノードを開閉する
+    pub fn toggle_node(&mut self) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.toggle();
+            self.show_tree();
+        }
     }
 
     ///入力欄切り替え
@@ -145,7 +311,13 @@ impl<'a> Events<'a> {
             let file = &self.items.concat()[next];
 
             // 開いているパスが同じフォルダの場合早期リターン
-            if self.path.iter().last() == Some(file) {
+            if self.path.iter().next_back() == Some(file) {
+                return;
+            }
+
+            // [Events::jump_link]と同じく、たどりきれないリンクだけ開かない
+            if let Some(link) = self.links.get(file).filter(|l| l.resolved.is_none()) {
+                self.message = Some(link.label());
                 return;
             }
 
@@ -166,188 +338,1685 @@ impl<'a> Events<'a> {
                     }
                 };
 
-                self.items = search_directory(dir);
+                self.set_items(dir);
+                self.filter.clear();
                 self.reset_state();
             } else {
                 //開いたものがファイルの場合
-                //開けるか確認
-                let text = read_to_string(self.path.as_path())
-                    .unwrap_or("ファイルが開けませんでした".to_string());
-
-                // ファイルの拡張子を取得
-                let extension = self
-                    .path
-                    .extension()
-                    .unwrap_or(OsStr::new("txt"))
-                    .to_str()
-                    .unwrap_or("txt");
-                self.data = syntax::hylight(text, extension, self.tab_width);
-                self.property = Property::new(&self.path);
-
-                // linemodeを初期化
-                self.line_mode = false;
+                self.load_file();
             }
             self.reset_substate();
         }
     }
 
-    ///現在開いているパスの一つ前のフォルダに戻る
-    #[inline]
-    pub fn back_file(&mut self) {
-        //! # Example
-        //! ``` rust
-        //! // パスが以下の通りなら
-        //! // C
-        //! // |--folder_01
-        //! //    |--folder_02
-        //! //       |--folder_03 <- 現在値1
-        //! //           |--file.txt <- 現在値2
-        //!
-        //! let mut event: Events = search_dir::Events::new();
-        //! event.back_file();
-        //!
-        //! // C
-        //! // |--folder_01
-        //! //    |--folder_02 <- 現在値1, 現在値2
-        //! ```
+    /// [Events::path]のファイルを読み込み[Events::data]に設定する。<br>
+    /// バイナリファイルは16進数表示にする
+    fn load_file(&mut self) {
+        // linemodeを初期化
+        self.line_mode = self.config.line_numbers;
+        self.markdown_mode = false;
+        self.tree = None;
+        self.table = None;
+        self.diff = None;
+        self.blame = false;
+        self.revision = None;
+        self.search.clear();
+        self.message = None;
+        self.property = Property::new(&self.path);
+        self.file_size = self.path.metadata().map(|m| m.len()).unwrap_or(0);
 
-        // 開いているものがファイルの場合はもう一階層戻る
-        if self.path.is_file() {
-            self.path.pop();
+        let head = encoding::sniff(&self.path);
+        self.encoding = encoding::detect(&head);
+        self.hex_mode = hex::is_binary(&head, self.encoding);
+        self.syntax = self.detect_syntax(&head);
+
+        self.load_content();
+    }
+
+    /// [Events::hex_mode]に合わせてファイルの中身を読み込む。<br>
+    /// [LAZY_THRESHOLD]より大きいファイルと16進数表示は表示する行だけを読み込む
+    fn load_content(&mut self) {
+        self.lazy = None;
+        self.highlight = None;
+        self.window_start = 0;
+        self.data = Text::default();
+
+        if self.hex_mode {
+            self.load_visible_lines();
+            return;
         }
 
-        let this_folder: OsString = self.path.iter().last().unwrap().to_os_string();
+        if let Some(property) = self.property.as_mut() {
+            property.set_encoding(self.encoding.name());
+        }
 
-        self.path.pop();
+        if self.tree.is_some() {
+            self.show_tree();
+            return;
+        }
 
-        self.items = search_directory(self.path.as_path().read_dir().unwrap());
+        if self.diff.is_some() {
+            self.show_diff();
+            return;
+        }
 
-        // 選択肢を現在のフォルダに選択
-        if self.path.file_name().is_some() {
-            let this_state = self.items[0]
-                .iter()
-                .position(|f| Into::<OsString>::into(f) == this_folder)
-                .unwrap();
-            self.state.select(Some(this_state));
+        if self.blame || self.revision.is_some() {
+            self.show_git();
+            return;
+        }
+
+        if self.file_size > LAZY_THRESHOLD {
+            match LazyFile::open(&self.path, self.encoding) {
+                Ok(lazy) => {
+                    self.lazy = Some(lazy);
+                    self.load_visible_lines();
+                }
+                Err(_) => self.data = Text::from("ファイルが開けませんでした"),
+            }
+            return;
+        }
+
+        //開けるか確認
+        let text = match fs::read(self.path.as_path()) {
+            Ok(buf) => encoding::decode(&buf, self.encoding),
+            Err(_) => "ファイルが開けませんでした".to_string(),
+        };
+
+        if self.markdown_mode {
+            self.markdown_width = self.view_width();
+            self.data = markdown::render(
+                &text,
+                self.markdown_width,
+                self.config.tab_width,
+                &self.config.theme,
+            );
+            if self.line_mode {
+                self.insert_line_numbers();
+            }
+            return;
         }
+        self.highlight_text(text);
     }
 
-    /// カウントディレクトリを変更する
-    pub fn _move_dir(&mut self) {
-        if self.path.is_file() {
-            self.path.pop();
+    /// JSON・YAML・TOMLを木構造で表示するかを切り替える
+    pub fn change_tree_mode(&mut self) {
+        if self.tree.is_none() {
+            let format = Format::detect(&self.path, self.syntax);
+            let Some(format) = format.filter(|_| self.path.is_file() && !self.hex_mode) else {
+                self.message = Some("JSON・YAML・TOMLのファイルではありません".to_string());
+                return;
+            };
+            if self.file_size > LAZY_THRESHOLD {
+                self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+                return;
+            }
+
+            let text = match fs::read(self.path.as_path()) {
+                Ok(buf) => encoding::decode(&buf, self.encoding),
+                Err(err) => {
+                    self.message = Some(err.to_string());
+                    return;
+                }
+            };
+            match Tree::parse(&text, format) {
+                Ok(tree) => self.tree = Some(tree),
+                Err(err) => {
+                    self.message = Some(format!("解析できませんでした: {err}"));
+                    return;
+                }
+            }
+            self.markdown_mode = false;
+            self.table = None;
+            self.diff = None;
+            self.blame = false;
+            self.revision = None;
+        } else {
+            self.tree = None;
         }
 
-        std::process::Command::new("cd")
-            .env("PATH", self.path.to_str().unwrap())
-            .spawn()
-            .unwrap();
+        self.message = None;
+        self.search.clear();
+        self.reset_substate();
+        self.load_content();
     }
 
-    /// [Events::state]\(選択\)を０にする
-    #[inline]
-    fn reset_state(&mut self) {
-        self.state.select(Some(0));
+    /// 木構造を[Events::data]に設定し、カーソルが見えるようにスクロールする
+    fn show_tree(&mut self) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+
+        let cursor = tree.cursor;
+        self.data = Text::from(tree.lines());
+        if self.line_mode {
+            self.insert_line_numbers();
+        }
+
+        let height = view_height().max(1);
+        if cursor < self.substate.0 {
+            self.substate.0 = cursor;
+        } else if cursor >= self.substate.0 + height {
+            self.substate.0 = cursor + 1 - height;
+        }
     }
 
-    /// [Events::substate]を(0,0)に設定する
-    #[inline]
-    pub fn reset_substate(&mut self) {
-        self.substate = (0, 0);
+    /// CSV・TSVを表で表示するかを切り替える
+    pub fn change_table_mode(&mut self) {
+        if self.table.is_some() {
+            self.table = None;
+            self.reset_substate();
+            return;
+        }
+
+        let delimiter = table::delimiter(&self.path, self.syntax);
+        let Some(delimiter) = delimiter.filter(|_| self.path.is_file() && !self.hex_mode) else {
+            self.message = Some("CSV・TSVのファイルではありません".to_string());
+            return;
+        };
+        if self.file_size > LAZY_THRESHOLD {
+            self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+            return;
+        }
+
+        let text = match fs::read(self.path.as_path()) {
+            Ok(buf) => encoding::decode(&buf, self.encoding),
+            Err(err) => {
+                self.message = Some(err.to_string());
+                return;
+            }
+        };
+        match CsvTable::parse(&text, delimiter) {
+            Ok(table) => {
+                if self.tree.is_some() || self.markdown_mode || self.diff.is_some() || self.blame {
+                    self.tree = None;
+                    self.markdown_mode = false;
+                    self.diff = None;
+                    self.blame = false;
+                    self.revision = None;
+                    self.load_content();
+                }
+                self.table = Some(table);
+                self.message = None;
+                self.search.clear();
+                self.reset_substate();
+            }
+            Err(err) => self.message = Some(format!("解析できませんでした: {err}")),
+        }
     }
 
-    /// linemode切り替え
-    pub fn change_linemode(&mut self) {
-        self.line_mode = !self.line_mode;
-        let line = self.data.lines.len();
+    /// 表表示の場合、左端に表示している列で並べ替える
+    pub fn sort_table(&mut self) {
+        if let Some(table) = self.table.as_mut() {
+            table.sort_by(self.substate.1 as usize);
+            self.substate.0 = 0;
+        }
+    }
 
-        if line == 0 {
+    /// Markdownを装飾して表示するかを切り替える
+    pub fn change_markdown_mode(&mut self) {
+        let markdown = matches!(self.syntax, Some("Markdown" | "MultiMarkdown"));
+        if !self.markdown_mode
+            && (!self.path.is_file() || self.hex_mode || self.lazy.is_some() || !markdown)
+        {
+            self.message = Some("Markdownのファイルではありません".to_string());
             return;
         }
 
-        if self.line_mode {
-            // 行数番号を表す"001 |"を挿入する
-            // ファイルの行数から桁数を求める
-            let digit_count = (line.ilog10() + 1) as usize;
+        self.markdown_mode = !self.markdown_mode;
+        self.tree = None;
+        self.table = None;
+        self.diff = None;
+        self.blame = false;
+        self.revision = None;
+        self.message = None;
+        self.search.clear();
+        self.reset_substate();
+        self.load_content();
+    }
+
+    /// `dir`の中身を一覧に設定する。Gitの作業ツリーの中ならファイルの状態も読み込む。<br>
+    /// 状態は別のリポジトリに移った時と[Events::refresh]でだけ読み込み直す
+    fn set_items(&mut self, dir: ReadDir) {
+        let path = self.dir().to_path_buf();
+        if !self.git.as_mut().is_some_and(|git| git.change_dir(&path)) {
+            self.git = GitStatus::load(&path);
+        }
+
+        let mut items = search_directory(dir, self.config.show_hidden, self.sort);
+        if self.config.respect_ignore {
+            let names = not_ignored(self.dir());
+            for items in items.iter_mut() {
+                items.retain(|name| names.contains(name));
+            }
+        }
+        if let Some(git) = self.git.as_ref().filter(|_| self.changed_only) {
+            for names in items.iter_mut() {
+                names.retain(|name| git.get(name).is_some_and(Status::is_changed));
+            }
+        }
+        self.items = items;
+        self.load_links();
+        self.load_details();
+    }
+
+    /// 一覧にあるシンボリックリンクを読み込む
+    fn load_links(&mut self) {
+        let dir = self.dir().to_path_buf();
+        self.links = self
+            .items
+            .concat()
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), Symlink::read(&dir.join(name))?)))
+            .collect();
+    }
 
-            for i in 0..line {
-                let line_text = format!("{:0>digit_count$} |", i + 1);
-                let line_span = Span::raw(line_text);
-                self.data.lines[i].spans.insert(0, line_span);
+    /// 一覧で選択しているシンボリックリンクのリンク先を開く
+    pub fn jump_link(&mut self) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+        let Some(link) = Symlink::read(&path) else {
+            self.message = Some("シンボリックリンクではありません".to_string());
+            return;
+        };
+        let Some(resolved) = link.resolved else {
+            self.message = Some(link.label());
+            return;
+        };
+
+        if !resolved.is_dir() {
+            self.open_path(resolved);
+            return;
+        }
+        match resolved.read_dir() {
+            Ok(dir) => {
+                self.path = resolved;
+                self.message = None;
+                self.set_items(dir);
+                self.filter.clear();
+                self.reset_state();
+                self.reset_substate();
+            }
+            Err(err) => self.message = Some(err.to_string()),
+        }
+    }
+
+    /// 表で表示する場合は一覧の各ファイル・フォルダのプロパティを読み込む
+    fn load_details(&mut self) {
+        self.details.clear();
+        if !self.detail {
+            return;
+        }
+        let dir = self.dir().to_path_buf();
+        for name in self.items.concat() {
+            if let Some(property) = Property::new(&dir.join(&name)) {
+                self.details.insert(name, property);
             }
+        }
+    }
+
+    /// 一覧を読み込み直し、選択していたものがあれば選択し直す
+    fn reload_items(&mut self) {
+        let Ok(dir) = self.dir().read_dir() else {
+            return;
+        };
+        let selected = self
+            .state
+            .selected()
+            .and_then(|i| self.items.concat().get(i).cloned());
+
+        self.set_items(dir);
+        self.filter.clear();
+
+        let position =
+            selected.and_then(|name| self.items.concat().iter().position(|f| *f == name));
+        match position {
+            Some(i) => self.state.select(Some(i)),
+            None if self.items.concat().is_empty() => self.state.select(None),
+            None => self.reset_state(),
+        }
+    }
+
+    /// 一覧の`num`番目(0: フォルダ, 1: ファイル)にある`name`を選択する。<br>
+    /// 隠しファイルなどで一覧に無い場合は一覧の並べ方に合わせて加えてから選択する
+    fn select_item(&mut self, num: usize, name: OsString) {
+        let position = match self.items[num].iter().position(|f| *f == name) {
+            Some(i) => i,
+            None => {
+                if let Some(link) = Symlink::read(&self.dir().join(&name)) {
+                    self.links.insert(name.clone(), link);
+                }
+                if let Some(property) =
+                    Property::new(&self.dir().join(&name)).filter(|_| self.detail)
+                {
+                    self.details.insert(name.clone(), property);
+                }
+                // 一覧と同じ並べ方の位置に入れる
+                let dir = self.dir().to_path_buf();
+                let mut paths: Vec<PathBuf> = self.items[num].iter().map(|f| dir.join(f)).collect();
+                paths.push(dir.join(&name));
+                self.sort.apply(&mut paths);
+                self.items[num] = paths
+                    .into_iter()
+                    .map(|path| path.file_name().unwrap().to_os_string())
+                    .collect();
+                self.items[num].iter().position(|f| *f == name).unwrap()
+            }
+        };
+        let offset = if num == 1 { self.items[0].len() } else { 0 };
+        self.state.select(Some(position + offset));
+    }
+
+    /// ファイル一覧を並べる基準を 名前 → 大きさ → 更新日時 → 拡張子 の順に切り替える
+    pub fn change_list_sort(&mut self) {
+        self.sort.next_key();
+        self.reload_items();
+    }
+
+    /// ファイル一覧を逆順にするかを切り替える
+    pub fn reverse_list_sort(&mut self) {
+        self.sort.reverse = !self.sort.reverse;
+        self.reload_items();
+    }
+
+    /// ファイル一覧を表で表示するかを切り替える
+    pub fn change_detail(&mut self) {
+        self.detail = !self.detail;
+        self.reload_items();
+    }
+
+    /// ファイル一覧の幅(%)。表で表示する場合は列が収まるように広げる
+    pub fn list_width(&self) -> u16 {
+        if self.detail {
+            self.config.list_width.max(DETAIL_LIST_WIDTH)
         } else {
-            // 行列番号のspanを削除する
-            for i in 0..line {
-                self.data.lines[i].spans.remove(0);
+            self.config.list_width
+        }
+    }
+
+    /// 隠しファイルを一覧に表示するかを切り替える
+    pub fn change_hidden(&mut self) {
+        self.config.show_hidden = !self.config.show_hidden;
+        self.message = Some(
+            if self.config.show_hidden {
+                "隠しファイルを表示"
+            } else {
+                "隠しファイルを非表示"
+            }
+            .to_string(),
+        );
+        self.reload_items();
+    }
+
+    /// .gitignore・.ignoreで無視されるものを一覧から除くかを切り替える
+    pub fn change_ignore(&mut self) {
+        self.config.respect_ignore = !self.config.respect_ignore;
+        self.message = Some(
+            if self.config.respect_ignore {
+                ".gitignore・.ignoreで無視されるものを非表示"
+            } else {
+                ".gitignore・.ignoreで無視されるものも表示"
             }
+            .to_string(),
+        );
+        self.reload_items();
+    }
+
+    /// ファイル一覧とGitでの状態を読み込み直す
+    pub fn refresh(&mut self) {
+        self.git = None;
+        self.reload_items();
+        self.message = Some("読み込み直しました".to_string());
+    }
+
+    /// Gitで変更のあるファイルだけを一覧に表示するかを切り替える
+    pub fn change_changed_only(&mut self) {
+        if self.git.is_none() {
+            self.message = Some("Gitの作業ツリーではありません".to_string());
+            return;
         }
+
+        self.changed_only = !self.changed_only;
+        self.reload_items();
     }
 
-    /// submodeが[true]のときファイルの先頭に移動する
-    #[inline]
-    pub fn move_first_line(&mut self) {
-        if self.submode {
-            self.substate = (0, 0);
+    /// 各行を最後に変更したコミットを表示するかを切り替える
+    pub fn change_blame_mode(&mut self) {
+        if !self.blame && (!self.path.is_file() || self.hex_mode) {
+            self.message = Some("ファイルを開いてください".to_string());
+            return;
+        }
+        if !self.blame && self.file_size > LAZY_THRESHOLD {
+            self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+            return;
         }
+
+        self.blame = !self.blame;
+        self.markdown_mode = false;
+        self.tree = None;
+        self.table = None;
+        self.diff = None;
+        self.message = None;
+        self.search.clear();
+        self.load_content();
     }
 
-    /// submodeが[true]のときファイルの行末に移動する
-    #[inline]
-    pub fn move_last_line(&mut self) {
-        if self.submode {
-            self.substate = (self.limit_down_size() as u16, 0);
+    /// 開いているファイルの履歴を表示する。コミットでのファイルを表示中の場合は今のファイルに戻る
+    pub fn start_log(&mut self) {
+        if self.revision.take().is_some() {
+            self.reset_substate();
+            self.load_content();
+            return;
+        }
+        if !self.path.is_file() {
+            self.message = Some("ファイルを開いてください".to_string());
+            return;
+        }
+        if self.file_size > LAZY_THRESHOLD {
+            self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+            return;
+        }
+
+        if let Err(err) = self.log.run(&self.path) {
+            self.message = Some(err);
         }
     }
 
-    /// ターミナル上に表示できる最大の下げ幅を取得
-    #[inline]
-    fn limit_down_size(&self) -> usize {
-        self.data
-            .lines
-            .len()
-            .saturating_sub(terminal::size().unwrap().1 as usize - 6)
+    /// 履歴で選んだコミットでのファイルを表示する
+    pub fn open_log_commit(&mut self) {
+        let Some(commit) = self.log.selected().cloned() else {
+            return;
+        };
+
+        self.log.close();
+        self.revision = Some(commit);
+        self.submode = true;
+        self.hex_mode = false;
+        self.markdown_mode = false;
+        self.tree = None;
+        self.table = None;
+        self.diff = None;
+        self.message = None;
+        self.search.clear();
+        self.reset_substate();
+        self.load_content();
     }
-}
 
-#[inline]
-/// 引数[ReadDir]からフォルダ・ファイルを取得し、\[Vec\<OsString\>; 2\]を返す。<br>
-/// 順番は\[フォルダ、ファイル\]の順
-pub fn search_directory(dir: ReadDir) -> [Vec<OsString>; 2] {
-    //! # Examples
-    //! ```rust
-    //! // カウントディレクトリに[a(file),b(folder),c(file),d(folder)]が入っているとすると
-    //! let path: ReadDir = std::env::current_dir()
-    //!     .unwrap()
-    //!     .read_dir()
-    //!     .unwrap();
-    //!
-    //! let mut list: Vec<String> = search_directory(path);
-    //! asset_eq!(list, Vec[b,d,a,c]);
-    //! ```
-    let mut folder: Vec<OsString> = Vec::with_capacity(30);
-    let mut file: Vec<OsString> = Vec::with_capacity(50);
+    /// 履歴で選んだコミットでのファイルや、各行を最後に変更したコミットを[Events::data]に設定する。<br>
+    /// [LAZY_THRESHOLD]より大きい場合は通常の表示に戻す
+    fn show_git(&mut self) {
+        let text = match &self.revision {
+            Some(commit) => commit.content(),
+            None => fs::read(&self.path).map_err(|err| err.to_string()),
+        };
+        let text = match text {
+            Ok(buf) if buf.len() as u64 > LAZY_THRESHOLD => {
+                self.blame = false;
+                self.revision = None;
+                self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+                self.load_content();
+                return;
+            }
+            Ok(buf) => encoding::decode(&buf, self.encoding),
+            Err(err) => {
+                self.data = Text::from(err);
+                return;
+            }
+        };
 
-    for data in dir {
-        let path = data.unwrap().path();
+        self.data = syntax::hylight(text, self.syntax, self.config.tab_width, &self.config.theme);
+
+        if self.blame {
+            match git_cmd::blame(&self.path, self.revision.as_ref()) {
+                Ok(blames) => {
+                    let style = Style::default().fg(Color::DarkGray);
+                    for (i, line) in self.data.lines.iter_mut().enumerate() {
+                        let label = blames.get(i).map(|blame| blame.label()).unwrap_or_default();
+                        line.spans.insert(0, Span::styled(label + " ", style));
+                    }
+                }
+                Err(err) => {
+                    self.blame = false;
+                    self.message = Some(err);
+                }
+            }
+        }
+
+        if self.line_mode {
+            self.insert_line_numbers();
+        }
+    }
 
-        let name = path.file_name().unwrap().to_os_string();
+    /// 一覧で`name`のGitでの状態
+    pub fn git_status(&self, name: &OsStr) -> Option<Status> {
+        self.git.as_ref()?.get(name)
+    }
 
-        if path.is_dir() {
-            folder.push(name);
+    /// 一覧で選択しているファイルに差分の比較元として印を付ける。同じファイルなら印を外す
+    pub fn mark_file(&mut self) {
+        let Some(path) = self.selected_path().filter(|path| path.is_file()) else {
+            self.message = Some("ファイルを選択してください".to_string());
+            return;
+        };
+
+        if self.marked.as_ref() == Some(&path) {
+            self.marked = None;
+            self.message = Some("印を外しました".to_string());
         } else {
-            file.push(name);
+            self.message = Some(format!(
+                "比較元: {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            self.marked = Some(path);
         }
     }
 
-    [folder, file]
-}
+    /// 一覧で`name`に印が付いているかどうか
+    pub fn is_marked(&self, name: &OsStr) -> bool {
+        self.marked.as_ref().is_some_and(|marked| {
+            marked.file_name() == Some(name) && marked.parent() == Some(self.dir())
+        })
+    }
 
-#[test]
-fn back_file_test() {
-    let mut x = Events::new(4);
-    x.back_file();
+    /// 印を付けたファイルと選択しているファイルの差分を 統合表示 → 左右表示 → 差分なし の順に切り替える
+    pub fn change_diff_mode(&mut self) {
+        let mode = match self.diff.as_ref().map(|diff| diff.mode) {
+            None => DiffMode::Unified,
+            Some(DiffMode::Unified) => DiffMode::SideBySide,
+            Some(DiffMode::SideBySide) => {
+                self.diff = None;
+                self.reset_substate();
+                self.load_content();
+                return;
+            }
+        };
 
-    let mut a = env::current_dir().unwrap();
-    a.pop();
-    assert_eq!(x.path, a);
+        if let Some(diff) = self.diff.as_mut() {
+            diff.mode = mode;
+            self.load_content();
+            return;
+        }
+
+        let Some(marked) = self.marked.clone() else {
+            self.message = Some("比較元のファイルに印を付けてください".to_string());
+            return;
+        };
+        // 一覧で選んでいるファイルを開いてから比べる
+        if !self.submode && self.selected_path().is_some_and(|path| path.is_file()) {
+            self.open_file();
+        }
+        if !self.path.is_file() || self.path == marked {
+            self.message = Some("比較するファイルを選択してください".to_string());
+            return;
+        }
+
+        self.hex_mode = false;
+        self.markdown_mode = false;
+        self.tree = None;
+        self.table = None;
+        self.blame = false;
+        self.revision = None;
+        self.diff = Some(Diff::new(marked, mode));
+        self.message = None;
+        self.search.clear();
+        self.reset_substate();
+        self.load_content();
+    }
+
+    /// 差分を[Events::data]に設定する。<br>
+    /// どちらかが[LAZY_THRESHOLD]より大きい場合は通常の表示に戻す
+    fn show_diff(&mut self) {
+        let width = self.view_width();
+        let Some(diff) = self.diff.as_mut() else {
+            return;
+        };
+
+        let size = |path: &Path| path.metadata().map_or(0, |m| m.len());
+        if size(&diff.old).max(size(&self.path)) > LAZY_THRESHOLD {
+            self.diff = None;
+            self.message = Some("ファイルが大きすぎるため表示できません".to_string());
+            self.load_content();
+            return;
+        }
+
+        let read = |path: &Path, encoding: Option<&'static Encoding>| {
+            let buf = fs::read(path)?;
+            let encoding = encoding.unwrap_or_else(|| encoding::detect(&encoding::sniff(path)));
+            Ok::<String, std::io::Error>(encoding::decode(&buf, encoding))
+        };
+        let (old, new) = match (read(&diff.old, None), read(&self.path, Some(self.encoding))) {
+            (Ok(old), Ok(new)) => (old, new),
+            (Err(err), _) | (_, Err(err)) => {
+                self.data = Text::from(err.to_string());
+                return;
+            }
+        };
+
+        self.line_mode = false;
+        self.data = diff.render(
+            &old,
+            &new,
+            width,
+            self.syntax,
+            self.config.tab_width,
+            &self.config.theme,
+        );
+    }
+
+    /// 次の変更箇所に移動する
+    pub fn next_hunk(&mut self) {
+        let top = self.substate.0;
+        let next = self
+            .diff
+            .as_ref()
+            .and_then(|diff| diff.hunks.iter().find(|&&hunk| hunk > top).copied());
+        if let Some(line) = next {
+            self.scroll_to(line);
+        }
+    }
+
+    /// 前の変更箇所に移動する
+    pub fn prev_hunk(&mut self) {
+        let top = self.substate.0;
+        let prev = self
+            .diff
+            .as_ref()
+            .and_then(|diff| diff.hunks.iter().rev().find(|&&hunk| hunk < top).copied());
+        if let Some(line) = prev {
+            self.scroll_to(line);
+        }
+    }
+
+    /// 折り返し表示を 折り返さない → 単語 → 文字 の順に切り替える
+    pub fn change_wrap_mode(&mut self) {
+        self.wrap = self.wrap.next();
+        // 折り返し中は横にスクロールしない
+        self.scroll_to(self.substate.0);
+    }
+
+    /// 折り返して表示するかどうか。16進数・表・木構造・プロパティの表示では折り返さない
+    pub fn is_wrapping(&self) -> bool {
+        self.wrap != WrapMode::Off
+            && !self.hex_mode
+            && !self.property_mode
+            && self.table.is_none()
+            && self.tree.is_none()
+            && self.diff.is_none()
+            && !self.blame
+    }
+
+    /// `line`を表示欄の幅で折り返す。<br>
+    /// 行番号は最初の行にだけ表示し、続きの行は行番号の幅だけ字下げする
+    pub fn wrap_line<'b>(&self, line: Line<'b>) -> Vec<Line<'b>> {
+        let mut spans = line.spans;
+        let number = if self.line_mode && !spans.is_empty() {
+            Some(spans.remove(0))
+        } else {
+            None
+        };
+        let indent = number.as_ref().map_or(0, Span::width);
+        let width = self.view_width().saturating_sub(indent);
+
+        wrap::wrap(spans, width, self.wrap == WrapMode::Word)
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut row)| {
+                match (&number, i) {
+                    (Some(number), 0) => row.insert(0, number.clone()),
+                    (Some(_), _) => row.insert(0, Span::raw(" ".repeat(indent))),
+                    (None, _) => {}
+                }
+                Line::from(row).style(line.style)
+            })
+            .collect()
+    }
+
+    /// 先に色なしで表示し、バックグラウンドでハイライトし終わった行から色を付ける
+    fn highlight_text(&mut self, text: String) {
+        self.data = Text::from(syntax::expand_tabs(text.clone(), self.config.tab_width));
+        self.highlight = BackgroundHighlight::spawn(
+            text,
+            self.syntax,
+            self.config.tab_width,
+            &self.config.theme,
+        );
+        if self.line_mode {
+            self.insert_line_numbers();
+        }
+    }
+
+    /// 設定ファイルの`[syntax]` → ファイル名 → 一行目 の順にシンタックスを判定する
+    fn detect_syntax(&self, head: &[u8]) -> Option<&'static str> {
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        self.config.syntax_for(&file_name).or_else(|| {
+            let text = encoding::decode(head, self.encoding);
+            syntax::detect_syntax(&file_name, text.lines().next().unwrap_or(""))
+        })
+    }
+
+    /// シンタックスの指定入力を開始する
+    pub fn start_syntax(&mut self) {
+        if !self.path.is_file() || self.hex_mode {
+            return;
+        }
+        self.syntax_input = Some(String::new());
+    }
+
+    /// シンタックスの指定入力を処理する。<br>
+    /// Enterで指定したシンタックスでハイライトし直す。空の場合は自動で判定し直す
+    pub fn syntax_input(&mut self, input: TextInput) {
+        let Some(token) = self.syntax_input.as_mut() else {
+            return;
+        };
+
+        match input {
+            TextInput::Char(c) => token.push(c),
+            TextInput::Backspace => {
+                token.pop();
+            }
+            TextInput::Enter => {
+                let token = self.syntax_input.take().unwrap_or_default();
+                let syntax = if token.trim().is_empty() {
+                    Some(self.detect_syntax(&encoding::sniff(&self.path)))
+                } else {
+                    syntax::syntax_name(&token).map(Some)
+                };
+
+                match syntax {
+                    Some(syntax) => {
+                        self.syntax = syntax;
+                        self.message = Some(format!("シンタックス: {}", syntax.unwrap_or("なし")));
+                        // 差分・blameなども含めて、表示中のものをそれぞれ読み込み直す
+                        self.load_content();
+                    }
+                    None => self.message = Some(format!("不明なシンタックス: {token}")),
+                }
+            }
+            TextInput::Cancel => self.syntax_input = None,
+            TextInput::Toggle | TextInput::None => {}
+        }
+    }
+
+    /// バックグラウンドでハイライトし終わった行を[Events::data]に反映する
+    pub fn apply_highlight(&mut self) {
+        let Some(highlight) = &self.highlight else {
+            return;
+        };
+
+        let (chunks, finished) = highlight.receive();
+        for (start, lines) in chunks {
+            for (line, mut highlighted) in self.data.lines.iter_mut().skip(start).zip(lines) {
+                // 行数番号のspanは残す
+                if self.line_mode {
+                    highlighted.spans.insert(0, line.spans[0].clone());
+                }
+                *line = highlighted;
+            }
+        }
+
+        if finished {
+            self.highlight = None;
+        }
+    }
+
+    /// テーマを次のものに切り替え、設定ファイルに保存する。<br>
+    /// ファイルを開いている場合はハイライトし直す
+    pub fn change_theme(&mut self) {
+        self.config.theme = syntax::next_theme(&self.config.theme);
+
+        self.message = Some(match Config::save_theme(&self.config.theme) {
+            Ok(()) => format!("テーマ: {}", self.config.theme),
+            Err(err) => format!(
+                "テーマ: {} (保存できませんでした: {err})",
+                self.config.theme
+            ),
+        });
+
+        if self.path.is_file() && !self.hex_mode {
+            self.load_content();
+        }
+    }
+
+    /// 文字コードの指定入力を開始する
+    pub fn start_encoding(&mut self) {
+        if !self.path.is_file() {
+            return;
+        }
+        self.encoding_input = Some(String::new());
+    }
+
+    /// 文字コードの指定入力を処理する。<br>
+    /// Enterで指定した文字コードで読み込み直す
+    pub fn encoding_input(&mut self, input: TextInput) {
+        let Some(label) = self.encoding_input.as_mut() else {
+            return;
+        };
+
+        match input {
+            TextInput::Char(c) => label.push(c),
+            TextInput::Backspace => {
+                label.pop();
+            }
+            TextInput::Enter => {
+                match encoding::find(label) {
+                    Some(encoding) => {
+                        self.encoding = encoding;
+                        self.message = None;
+                        self.hex_mode = false;
+                        self.search.clear();
+                        self.reset_substate();
+                        self.load_content();
+                    }
+                    None => self.message = Some(format!("不明な文字コード: {label}")),
+                }
+                self.encoding_input = None;
+            }
+            TextInput::Cancel => self.encoding_input = None,
+            TextInput::Toggle | TextInput::None => {}
+        }
+    }
+
+    /// 大きなファイルや16進数表示の場合、表示する範囲の行が
+    /// [Events::data]に無ければ前後[WINDOW_MARGIN]行と合わせて読み込む。<br>
+    /// Markdown・差分の表示中に表示欄の幅が変わった場合は表示し直す
+    pub fn load_visible_lines(&mut self) {
+        if let Some(diff) = &self.diff {
+            if diff.width != self.view_width() {
+                self.load_content();
+            }
+            return;
+        }
+
+        if self.markdown_mode && !self.hex_mode {
+            if self.markdown_width != self.view_width() {
+                self.load_content();
+            }
+            return;
+        }
+
+        if !self.hex_mode && self.lazy.is_none() {
+            return;
+        }
+
+        let height = view_height();
+        let top = self.substate.0;
+        let end = self.window_start + self.data.lines.len();
+        let loaded = !self.data.lines.is_empty()
+            && top >= self.window_start
+            && (top + height <= end || end >= self.line_count());
+        if loaded {
+            return;
+        }
+
+        let start = top.saturating_sub(WINDOW_MARGIN);
+        let count = height + WINDOW_MARGIN * 2;
+        self.window_start = start;
+
+        if self.hex_mode {
+            self.data = match hex::dump(&self.path, start, count) {
+                Ok(lines) => Text::from(lines),
+                Err(err) => Text::from(err.to_string()),
+            };
+        } else if let Some(lazy) = &self.lazy {
+            let lines = match lazy.read_lines(start, count) {
+                Ok(lines) => lines,
+                Err(err) => vec![err.to_string()],
+            };
+            // 表示する部分だけをハイライトする
+            self.data = syntax::hylight(
+                lines.join("\n"),
+                self.syntax,
+                self.config.tab_width,
+                &self.config.theme,
+            );
+        }
+
+        if self.line_mode {
+            self.insert_line_numbers();
+        }
+    }
+
+    /// 16進数表示を切り替える
+    pub fn change_hex_mode(&mut self) {
+        if !self.path.is_file() {
+            return;
+        }
+
+        self.hex_mode = !self.hex_mode;
+        self.tree = None;
+        self.table = None;
+        self.diff = None;
+        self.blame = false;
+        self.revision = None;
+        self.search.clear();
+        self.reset_substate();
+        self.load_content();
+    }
+
+    /// ファイルの行数
+    fn line_count(&self) -> usize {
+        if self.hex_mode {
+            return hex::row_count(self.file_size);
+        }
+        // 表表示の場合は見出しの行も含める
+        if let Some(table) = &self.table {
+            return table.len() + 1;
+        }
+
+        match &self.lazy {
+            Some(lazy) => lazy.line_count(),
+            None => self.data.lines.len(),
+        }
+    }
+
+    ///現在開いているパスの一つ前のフォルダに戻る
+    #[inline]
+    pub fn back_file(&mut self) {
+        //! # Example
+        //! ``` rust
+        //! // パスが以下の通りなら
+        //! // C
+        //! // |--folder_01
+        //! //    |--folder_02
+        //! //       |--folder_03 <- 現在値1
+        //! //           |--file.txt <- 現在値2
+        //!
+        //! let mut event: Events = search_dir::Events::new();
+        //! event.back_file();
+        //!
+        //! // C
+        //! // |--folder_01
+        //! //    |--folder_02 <- 現在値1, 現在値2
+        //! ```
+
+        // 開いているものがファイルの場合はもう一階層戻る
+        if self.path.is_file() {
+            self.path.pop();
+        }
+
+        let this_folder = self.path.file_name().map(|name| name.to_os_string());
+
+        self.path.pop();
+
+        self.set_items(self.path.as_path().read_dir().unwrap());
+        self.filter.clear();
+
+        // 選択肢を現在のフォルダに選択
+        if let Some(this_folder) = this_folder {
+            self.select_item(0, this_folder);
+        }
+    }
+
+    /// カウントディレクトリを変更する
+    #[allow(clippy::zombie_processes)]
+    pub fn _move_dir(&mut self) {
+        if self.path.is_file() {
+            self.path.pop();
+        }
+
+        std::process::Command::new("cd")
+            .env("PATH", self.path.to_str().unwrap())
+            .spawn()
+            .unwrap();
+    }
+
+    /// [Events::state]\(選択\)を０にする
+    #[inline]
+    fn reset_state(&mut self) {
+        self.state.select(Some(0));
+    }
+
+    /// [Events::substate]を(0,0)に設定する
+    #[inline]
+    pub fn reset_substate(&mut self) {
+        self.substate = (0, 0);
+        self.wrap_row = 0;
+    }
+
+    /// linemode切り替え
+    pub fn change_linemode(&mut self) {
+        // 差分は常に両方の行番号を表示している
+        if self.diff.is_some() {
+            return;
+        }
+
+        self.line_mode = !self.line_mode;
+
+        if self.line_mode {
+            self.insert_line_numbers();
+        } else {
+            // 行列番号のspanを削除する
+            for line in self.data.lines.iter_mut() {
+                line.spans.remove(0);
+            }
+        }
+    }
+
+    /// [Events::data]の各行に行数番号を表す"001 |"を挿入する
+    fn insert_line_numbers(&mut self) {
+        let line = self.line_count();
+
+        if line == 0 {
+            return;
+        }
+
+        // ファイルの行数から桁数を求める
+        let digit_count = (line.ilog10() + 1) as usize;
+
+        for (i, line) in self.data.lines.iter_mut().enumerate() {
+            let line_text = format!("{:0>digit_count$} |", self.window_start + i + 1);
+            line.spans.insert(0, Span::raw(line_text));
+        }
+    }
+
+    /// submodeが[true]のときファイルの先頭に移動する
+    #[inline]
+    pub fn move_first_line(&mut self) {
+        if self.submode {
+            self.reset_substate();
+        }
+    }
+
+    /// submodeが[true]のときファイルの行末に移動する
+    #[inline]
+    pub fn move_last_line(&mut self) {
+        if self.submode {
+            self.scroll_to(self.line_count());
+        }
+    }
+
+    /// ファイル内検索の入力を開始する
+    pub fn start_search(&mut self) {
+        if self.property_mode || self.hex_mode {
+            return;
+        }
+        // 表は並べ替えや列の位置がファイルの行と一致しないため検索しない
+        if self.table.is_some() {
+            self.message = Some("表の表示中は検索できません".to_string());
+            return;
+        }
+        self.search.clear();
+        self.search.input_mode = true;
+        self.search.origin = self.substate;
+    }
+
+    /// ファイル内検索の入力を処理する。<br>
+    /// 一文字入力されるごとに検索し、一致箇所までスクロールする。<br>
+    /// 大きなファイルはEnterを押した時にバックグラウンドで検索する
+    pub fn search_input(&mut self, input: TextInput) {
+        match input {
+            TextInput::Char(c) => self.search.query.push(c),
+            TextInput::Backspace => {
+                self.search.query.pop();
+            }
+            TextInput::Enter => {
+                self.search.input_mode = false;
+                if self.lazy.is_none() {
+                    return;
+                }
+            }
+            TextInput::Cancel => {
+                self.substate = self.search.origin;
+                self.wrap_row = 0;
+                self.search.clear();
+                return;
+            }
+            TextInput::Toggle | TextInput::None => return,
+        }
+
+        if self.lazy.is_some() && self.search.input_mode {
+            return;
+        }
+
+        if let Some(lazy) = &self.lazy {
+            // 大きなファイルは先頭から順番に読み込みながら探す
+            match expanded_lines(lazy, self.config.tab_width) {
+                Ok(lines) => self.search.find_background(lines),
+                Err(_) => self.search.find(Vec::<String>::new()),
+            }
+            return;
+        }
+        self.search.find(self.plain_lines());
+
+        let origin = self.search.origin.0;
+        match self.search.select_from(origin) {
+            Some(m) => self.scroll_to(m.line),
+            None => {
+                self.substate = self.search.origin;
+                self.wrap_row = 0;
+            }
+        }
+    }
+
+    /// 大きなファイルの検索結果を受け取り、検索開始時の位置以降で最初に見つかった一致箇所までスクロールする。<br>
+    /// まだ検索中なら[true]
+    pub fn poll_search(&mut self) -> bool {
+        let running = self.search.poll();
+        if self.search.current.is_some() || self.search.matches.is_empty() {
+            return running;
+        }
+
+        // 検索開始時の位置以降に見つかるか、検索が終わったら選択する
+        let origin = self.search.origin.0;
+        let found = self.search.matches.last().is_some_and(|m| m.line >= origin);
+        if found || !running {
+            if let Some(m) = self.search.select_from(origin) {
+                self.scroll_to(m.line);
+            }
+        }
+        running
+    }
+
+    /// 次の検索結果に移動する
+    pub fn search_next(&mut self) {
+        if let Some(m) = self.search.next() {
+            self.scroll_to(m.line);
+        }
+    }
+
+    /// 前の検索結果に移動する
+    pub fn search_prev(&mut self) {
+        if let Some(m) = self.search.prev() {
+            self.scroll_to(m.line);
+        }
+    }
+
+    /// ファイル一覧の絞り込み入力を開始する
+    pub fn start_filter(&mut self) {
+        if !self.filter.is_active() {
+            self.filter.source = self.items.clone();
+        }
+        self.filter.input_mode = true;
+    }
+
+    /// ファイル一覧の絞り込み入力を処理する。<br>
+    /// 一文字入力されるごとに[Events::items]を絞り込む
+    pub fn filter_input(&mut self, input: TextInput) {
+        match input {
+            TextInput::Char(c) => self.filter.query.push(c),
+            TextInput::Backspace => {
+                self.filter.query.pop();
+            }
+            TextInput::Enter => {
+                self.filter.input_mode = false;
+                return;
+            }
+            TextInput::Cancel => {
+                self.items = std::mem::take(&mut self.filter.source);
+                self.filter.clear();
+                self.reset_state();
+                return;
+            }
+            TextInput::Toggle | TextInput::None => return,
+        }
+
+        self.items = if self.filter.is_active() {
+            self.filter.apply()
+        } else {
+            self.filter.source.clone()
+        };
+
+        // 絞り込み後の一覧に合わせて選択し直す
+        if self.items.concat().is_empty() {
+            self.state.select(None);
+        } else {
+            self.reset_state();
+        }
+    }
+
+    /// フォルダ以下のファイルの中身の検索入力を開始する
+    pub fn start_grep(&mut self) {
+        self.grep.query.clear();
+        self.grep.input_mode = true;
+    }
+
+    /// フォルダ以下のファイルの中身の検索入力を処理する。<br>
+    /// Enterで[Events::path]以下を検索し結果を表示する
+    pub fn grep_input(&mut self, input: TextInput) {
+        match input {
+            TextInput::Char(c) => self.grep.query.push(c),
+            TextInput::Backspace => {
+                self.grep.query.pop();
+            }
+            TextInput::Toggle => self.grep.regex = !self.grep.regex,
+            TextInput::Enter => {
+                self.grep.input_mode = false;
+                if self.grep.query.is_empty() {
+                    return;
+                }
+
+                let mut root = self.path.clone();
+                if root.is_file() {
+                    root.pop();
+                }
+                self.grep.run(&root);
+            }
+            TextInput::Cancel => self.grep.input_mode = false,
+            TextInput::None => {}
+        }
+    }
+
+    /// 選択中の検索結果のファイルを開き、一致した行までスクロールする
+    pub fn open_grep_result(&mut self) {
+        let Some(result) = self.grep.selected().cloned() else {
+            return;
+        };
+
+        if self.open_path(result.path) {
+            self.property_mode = false;
+            self.submode = true;
+            self.grep.close();
+            self.scroll_to(result.line);
+        }
+    }
+
+    /// 指定したファイルを開き、ファイル一覧をそのファイルがあるフォルダにする。<br>
+    /// 開けなかった場合は[false]を返す
+    pub fn open_path(&mut self, path: PathBuf) -> bool {
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let Ok(dir) = parent.read_dir() else {
+            return false;
+        };
+
+        let name = path.file_name().map(OsStr::to_os_string);
+        self.path = path;
+        self.set_items(dir);
+        self.filter.clear();
+
+        // ファイル一覧の選択を開いたファイルに合わせる
+        match name {
+            Some(name) => self.select_item(1, name),
+            None => self.state.select(None),
+        }
+
+        self.load_file();
+        self.reset_substate();
+        true
+    }
+
+    /// ファイルの表示欄の見出しを取得する
+    pub fn title(&self) -> String {
+        let mut title = Vec::new();
+
+        if let Some(lazy) = &self.lazy {
+            if !lazy.is_indexed() {
+                title.push(format!("行数を確認中... {}行", lazy.line_count()));
+            }
+        }
+        if self.highlight.is_some() {
+            title.push("ハイライト中...".to_string());
+        }
+        if self.markdown_mode {
+            title.push("Markdown表示".to_string());
+        }
+        if self.is_wrapping() {
+            title.push(self.wrap.name().to_string());
+        }
+        if let Some(tree) = &self.tree {
+            title.push(tree.path());
+        }
+        if let Some(commit) = &self.revision {
+            title.push(format!(
+                "{} {} {} (Lで今のファイルに戻る)",
+                commit.short(),
+                commit.date,
+                commit.summary
+            ));
+        }
+        if let Some(diff) = &self.diff {
+            let name = |path: &Path| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            };
+            let position = diff
+                .hunks
+                .iter()
+                .filter(|&&hunk| hunk <= self.substate.0)
+                .count();
+            title.push(format!(
+                "差分: {} → {} +{} -{} 変更 {position}/{}",
+                name(&diff.old),
+                name(&self.path),
+                diff.added,
+                diff.removed,
+                diff.hunks.len()
+            ));
+        }
+        if let Some(status) = self.table.as_ref().and_then(CsvTable::sort_status) {
+            title.push(status);
+        }
+        if !self.search.query.is_empty() {
+            title.push(self.search.status());
+        }
+        if let Some(message) = &self.message {
+            title.push(message.clone());
+        }
+
+        title.join(" ")
+    }
+
+    /// 入力欄に表示する文字列を取得する。入力中でなければ[None]
+    pub fn prompt(&self) -> Option<String> {
+        if self.search.input_mode {
+            Some("/".to_string() + &self.search.query)
+        } else if self.filter.input_mode {
+            Some("/".to_string() + &self.filter.query)
+        } else if self.grep.input_mode {
+            let kind = if self.grep.regex {
+                "正規表現"
+            } else {
+                "文字列"
+            };
+            Some(format!("grep({kind}, Tabで切替): {}", self.grep.query))
+        } else if let Some(label) = &self.encoding_input {
+            Some(format!("文字コード: {label}"))
+        } else {
+            self.syntax_input
+                .as_ref()
+                .map(|token| format!("シンタックス(空欄で自動判定): {token}"))
+        }
+    }
+
+    /// ファイルの表示欄の枠を除いた幅
+    pub fn view_width(&self) -> usize {
+        let width = terminal::size().unwrap().0 as usize;
+        let list = width * self.list_width() as usize / 100;
+        // 一覧との分け方で1ずれることがあるので少し狭くする
+        width.saturating_sub(list + 3)
+    }
+
+    /// 一覧で選択しているファイル・フォルダのパス
+    fn selected_path(&self) -> Option<PathBuf> {
+        let name = self.items.concat().get(self.state.selected()?)?.clone();
+        Some(self.dir().join(name))
+    }
+
+    /// 一覧に表示しているフォルダ
+    fn dir(&self) -> &Path {
+        if self.path.is_file() {
+            self.path.parent().unwrap_or(&self.path)
+        } else {
+            &self.path
+        }
+    }
+
+    /// 指定した行が先頭になるようにスクロールする。<br>
+    /// 最後の行まで表示できる位置より下には行かない
+    fn scroll_to(&mut self, line: usize) {
+        if !self.is_wrapping() {
+            self.substate = (line.min(self.limit_down_size()), 0);
+            return;
+        }
+
+        let limit = self.wrapped_limit();
+        self.substate = (line.min(limit.0), 0);
+        self.wrap_row = if line >= limit.0 { limit.1 } else { 0 };
+    }
+
+    /// 折り返し中に、折り返した行一つ分下にスクロールする
+    fn scroll_down_wrapped(&mut self) {
+        if (self.substate.0, self.wrap_row) >= self.wrapped_limit() {
+            return;
+        }
+        if self.wrap_row + 1 < self.wrapped_rows(self.substate.0) {
+            self.wrap_row += 1;
+        } else {
+            self.substate.0 += 1;
+            self.wrap_row = 0;
+        }
+    }
+
+    /// 折り返し中に、折り返した行一つ分上にスクロールする
+    fn scroll_up_wrapped(&mut self) {
+        if self.wrap_row > 0 {
+            self.wrap_row -= 1;
+        } else if self.substate.0 > 0 {
+            self.substate.0 -= 1;
+            self.wrap_row = self.wrapped_rows(self.substate.0) - 1;
+        }
+    }
+
+    /// `line`行目を折り返した行数
+    fn wrapped_rows(&self, line: usize) -> usize {
+        self.display_lines(line, 1)
+            .into_iter()
+            .next()
+            .map_or(1, |line| self.wrap_line(line).len())
+    }
+
+    /// [Events::data]の各行の先頭にある、行番号やblameのspanの数
+    pub fn prefix_spans(&self) -> usize {
+        usize::from(self.line_mode) + usize::from(self.blame)
+    }
+
+    /// [Events::data]の各行を装飾・行番号なしの文字列で返す
+    fn plain_lines(&self) -> Vec<String> {
+        let skip = self.prefix_spans();
+
+        self.data
+            .lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .skip(skip)
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// ターミナル上に表示できる最大の下げ幅を取得。<br>
+    /// 折り返し中は最後の行まで表示できる一番上の行
+    fn limit_down_size(&self) -> usize {
+        if self.is_wrapping() {
+            return self.wrapped_limit().0;
+        }
+        self.line_count().saturating_sub(view_height())
+    }
+
+    /// 折り返し中に最後の行まで表示できる一番下のスクロール位置。<br>
+    /// (先頭の行, その行を折り返した何行目から表示するか)
+    fn wrapped_limit(&self) -> (usize, usize) {
+        let height = view_height();
+        // 末尾の行は高々height行あれば足りる
+        let start = self.line_count().saturating_sub(height);
+
+        // 末尾から折り返した行数を足していき、ちょうど表示欄が埋まる位置
+        let mut rows = 0;
+        for (i, line) in self
+            .display_lines(start, height)
+            .into_iter()
+            .enumerate()
+            .rev()
+        {
+            rows += self.wrap_line(line).len();
+            if rows >= height {
+                return (start + i, rows - height);
+            }
+        }
+        (start, 0)
+    }
+
+    /// `start`行目から`count`行を表示する形で取得する。<br>
+    /// 大きなファイルはファイルから読み込み、行番号を付ける
+    fn display_lines(&self, start: usize, count: usize) -> Vec<Line<'a>> {
+        let Some(lazy) = &self.lazy else {
+            return self
+                .data
+                .lines
+                .iter()
+                .skip(start)
+                .take(count)
+                .cloned()
+                .collect();
+        };
+
+        let digit_count = (self.line_count().max(1).ilog10() + 1) as usize;
+        lazy.read_lines(start, count)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let mut spans = vec![Span::raw(syntax::expand_tabs(text, self.config.tab_width))];
+                if self.line_mode {
+                    let number = format!("{:0>digit_count$} |", start + i + 1);
+                    spans.insert(0, Span::raw(number));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
+
+/// ファイルの表示欄に表示できる行数
+#[inline]
+fn view_height() -> usize {
+    (terminal::size().unwrap().1 as usize).saturating_sub(6)
+}
+
+/// 大きなファイルの各行を表示と同じようにタブを展開して返す。<br>
+/// 検索の一致箇所が表示している文字列の位置と合うようにする
+fn expanded_lines(
+    lazy: &LazyFile,
+    tab_width: usize,
+) -> io::Result<impl Iterator<Item = String> + Send> {
+    Ok(lazy
+        .lines()?
+        .map(move |line| syntax::expand_tabs(line, tab_width)))
+}
+
+/// `dir`の中で.gitignore・.ignore(親フォルダのものも含む)で無視されないものの名前
+pub fn not_ignored(dir: &Path) -> HashSet<OsString> {
+    WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        .hidden(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() == 1)
+        .map(|entry| entry.file_name().to_os_string())
+        .collect()
+}
+
+/// 引数[ReadDir]からフォルダ・ファイルを取得し、\[Vec\<OsString\>; 2\]を返す。<br>
+/// 順番は\[フォルダ、ファイル\]の順で、それぞれ`sort`で並べ替える<br>
+/// `show_hidden`が[false]の場合"."から始まるものは除く
+/// # Examples
+/// ```rust
+/// // カウントディレクトリに[c(file),d(folder),a(file),b(folder)]が入っているとすると
+/// let path: ReadDir = std::env::current_dir()
+///     .unwrap()
+///     .read_dir()
+///     .unwrap();
+///
+/// let mut list: Vec<String> = search_directory(path, true, ListSort::default());
+/// asset_eq!(list, Vec[b,d,a,c]);
+/// ```
+#[inline]
+pub fn search_directory(dir: ReadDir, show_hidden: bool, sort: ListSort) -> [Vec<OsString>; 2] {
+    let mut folder: Vec<PathBuf> = Vec::with_capacity(30);
+    let mut file: Vec<PathBuf> = Vec::with_capacity(50);
+
+    for data in dir {
+        let path = data.unwrap().path();
+
+        if !show_hidden && path.file_name().unwrap().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            folder.push(path);
+        } else {
+            file.push(path);
+        }
+    }
+
+    [folder, file].map(|mut paths| {
+        sort.apply(&mut paths);
+        paths
+            .into_iter()
+            .map(|path| path.file_name().unwrap().to_os_string())
+            .collect()
+    })
+}
+
+#[test]
+fn back_file_test() {
+    let mut x = Events::new(Config::default(), std::env::current_dir().unwrap());
+    x.back_file();
+
+    let mut a = std::env::current_dir().unwrap();
+    a.pop();
+    assert_eq!(x.path, a);
+
+    // ルートではそれ以上戻らず、一覧にも加えない
+    let mut x = Events::new(Config::default(), PathBuf::from("/"));
+    x.back_file();
+    assert_eq!(x.path, PathBuf::from("/"));
+    assert!(!x.items.concat().contains(&OsString::from("/")));
+}
+
+#[test]
+fn hidden_and_ignore_test() {
+    let root = std::env::temp_dir().join(format!("tui-cat-hidden-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join(".hidden")).unwrap();
+    fs::create_dir_all(root.join("build")).unwrap();
+    fs::write(root.join(".ignore"), "build/\n").unwrap();
+    fs::write(root.join("a.txt"), "").unwrap();
+
+    let names = not_ignored(&root);
+    assert!(names.contains(OsStr::new("a.txt")));
+    assert!(!names.contains(OsStr::new("build")));
+
+    // 隠しフォルダから戻っても選択し直せる
+    let config = Config {
+        show_hidden: false,
+        ..Config::default()
+    };
+    let mut x = Events::new(config, root.join(".hidden"));
+    x.back_file();
+    let selected = x.state.selected().map(|i| x.items.concat()[i].clone());
+    assert_eq!(selected, Some(OsString::from(".hidden")));
+    assert_eq!(
+        x.items[0],
+        [OsString::from(".hidden"), OsString::from("build")]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn expanded_lines_search_test() {
+    let path = std::env::temp_dir().join(format!("tui-cat-tab-{}.txt", std::process::id()));
+    fs::write(&path, "\tあx\n").unwrap();
+
+    let lazy = LazyFile::open(&path, UTF_8).unwrap();
+    let mut search = Search::default();
+    search.query = "x".to_string();
+    search.find_background(expanded_lines(&lazy, 4).unwrap());
+    while search.poll() {
+        std::thread::yield_now();
+    }
+
+    // タブの後に全角文字があっても表示している文字列の位置で一致する
+    let line = Line::from(syntax::expand_tabs("\tあx".to_string(), 4));
+    let highlighted = search.highlight_line(0, &line, 0);
+    let contents: Vec<&str> = highlighted
+        .spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    assert_eq!(contents, ["    あ", "x"]);
+
+    fs::remove_file(&path).unwrap();
 }
@@ -1,63 +1,452 @@
-use std::borrow::Cow;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, OnceLock,
+    },
+    thread,
+};
 
-use ratatui::text::{Line, Span, Text};
+use crate::config::Config;
 
+use ratatui::{
+    style::{Color, Modifier},
+    text::{Line, Span, Text},
+};
+
+use syntect::dumps::{dump_to_file, from_dump_file};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{FontStyle, Style, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+/// 初期設定のテーマ
+pub const DEFAULT_THEME: &str = "base16-eighties.dark";
+/// バックグラウンドでハイライトする際に、まとめて送る行数
+const CHUNK_LINES: usize = 200;
+/// 追加のシンタックス定義を組み込んだものを保存するキャッシュファイルの名前
+const SYNTAX_CACHE: &str = "syntaxes.packdump";
+
+/// 一度だけ読み込むシンタックス定義とテーマ
+static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+
+/// 読み込み済みのシンタックス定義とテーマ。<br>
+/// 読み込みに時間がかかるため、起動中は[Highlighter::get]で同じものを使い回す
+pub struct Highlighter {
+    /// シンタックス定義
+    pub syntaxes: SyntaxSet,
+    /// テーマ
+    pub themes: ThemeSet,
+    /// 追加のシンタックス定義が読み込めなかった場合のエラー
+    pub syntax_error: Option<String>,
+}
+
+impl Highlighter {
+    /// 初めて呼ばれた時に読み込み、以降は読み込み済みのものを返す
+    pub fn get() -> &'static Highlighter {
+        HIGHLIGHTER.get_or_init(|| {
+            let dir = Config::dir().map(|dir| dir.join("syntaxes"));
+            let cache = Config::cache_dir().map(|dir| dir.join(SYNTAX_CACHE));
+
+            let (syntaxes, syntax_error) = match dir {
+                Some(dir) if dir.is_dir() => match load_syntaxes(&dir, cache.as_deref()) {
+                    Ok(syntaxes) => (syntaxes, None),
+                    // 読み込めなくても組み込みのシンタックスは使えるようにする
+                    Err(err) => (SyntaxSet::load_defaults_nonewlines(), Some(err)),
+                },
+                _ => (SyntaxSet::load_defaults_nonewlines(), None),
+            };
+
+            Highlighter {
+                syntaxes,
+                themes: load_themes(),
+                syntax_error,
+            }
+        })
+    }
+}
+
+/// 組み込みのシンタックスに`dir`以下の`.sublime-syntax`ファイルを追加して読み込む。<br>
+/// 組み立てに時間がかかるため`cache`に保存しておき、`dir`のファイルが変わっていなければそちらを使う
+fn load_syntaxes(dir: &Path, cache: Option<&Path>) -> Result<SyntaxSet, String> {
+    let stamp = syntax_stamp(dir);
+
+    if let Some(cache) = cache {
+        if let Ok((cached, syntaxes)) = from_dump_file::<(String, SyntaxSet), _>(cache) {
+            if cached == stamp {
+                return Ok(syntaxes);
+            }
+        }
+    }
+
+    let mut builder = SyntaxSet::load_defaults_nonewlines().into_builder();
+    builder
+        .add_from_folder(dir, false)
+        .map_err(|err| format!("シンタックス定義を読み込めませんでした: {err}"))?;
+    let syntaxes = builder.build();
+
+    if let Some(cache) = cache {
+        // 保存できなくても次回組み立て直すだけなので無視する
+        let _ = cache
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| dump_to_file(&(stamp, &syntaxes), cache).map_err(std::io::Error::other));
+    }
+
+    Ok(syntaxes)
+}
+
+/// キャッシュが古くなっていないか確認するための、バージョンと`dir`以下の
+/// `.sublime-syntax`ファイルのパス・大きさ・更新日時をつなげた文字列
+fn syntax_stamp(dir: &Path) -> String {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "sublime-syntax") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut stamp = env!("CARGO_PKG_VERSION").to_string();
+    for path in files {
+        let meta = fs::metadata(&path).ok();
+        let modified = meta.as_ref().and_then(|m| m.modified().ok());
+        stamp += &format!(
+            "\n{}:{}:{:?}",
+            path.display(),
+            meta.map(|m| m.len()).unwrap_or(0),
+            modified
+        );
+    }
+    stamp
+}
 
-/// テキストがシンタックスハイライト可能であれば変更する
-pub fn hylight<'a, 'b, T1, T2>(text: T1, extension: T2, tab_width: usize) -> Text<'a>
+/// テキストがシンタックスハイライト可能であれば変更する。<br>
+/// タブはハイライトするかに関わらず展開する
+pub fn hylight<'a, T>(text: T, syntax: Option<&str>, tab_width: usize, theme: &str) -> Text<'a>
 where
-    T1: Into<Cow<'a, str>> + ToString,
-    T2: Into<Cow<'b, str>>,
+    T: ToString,
 {
-    // 実装は(https://docs.rs/syntect/latest/syntect/easy/struct.HighlightLines.html)参考
-    let ps = SyntaxSet::load_defaults_nonewlines();
-    let ts = ThemeSet::load_defaults();
+    let text = expand_tabs(text.to_string(), tab_width);
 
-    let syntax = match ps.find_syntax_by_extension(&extension.into()) {
-        Some(f) => f,
-        None => return Text::from(text.into()),
+    // 実装は(https://docs.rs/syntect/latest/syntect/easy/struct.HighlightLines.html)参考
+    let Some(syntax) = syntax.and_then(find_syntax) else {
+        return Text::from(text);
     };
+    let mut h = HighlightLines::new(syntax, find_theme(theme));
+
+    let lines: Vec<Line> = text
+        .lines()
+        .map(|line| highlight_line(&mut h, line))
+        .collect();
+
+    Text::from(lines)
+}
+
+/// バックグラウンドのスレッドで行うハイライト。<br>
+/// ハイライトした行は[CHUNK_LINES]行ずつ`(先頭の行番号, 行)`として送られてくる
+#[derive(Debug)]
+pub struct BackgroundHighlight {
+    /// ハイライトした行の受け取り口
+    receiver: Receiver<(usize, Vec<Line<'static>>)>,
+    /// ハイライトを中止するかどうか
+    cancel: Arc<AtomicBool>,
+}
+
+impl BackgroundHighlight {
+    /// `text`のハイライトを開始する。シンタックスが無い場合は[None]
+    pub fn spawn(
+        text: String,
+        syntax: Option<&str>,
+        tab_width: usize,
+        theme: &str,
+    ) -> Option<Self> {
+        let syntax = syntax.and_then(find_syntax)?;
+        let theme = find_theme(theme);
+
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceled = Arc::clone(&cancel);
+
+        thread::spawn(move || {
+            let mut h = HighlightLines::new(syntax, theme);
+            let mut chunk = Vec::with_capacity(CHUNK_LINES);
+            let mut start = 0;
+
+            for (i, line) in expand_tabs(text, tab_width).lines().enumerate() {
+                if canceled.load(Ordering::Acquire) {
+                    return;
+                }
 
-    let mut h = HighlightLines::new(syntax, &ts.themes["base16-eighties.dark"]);
+                chunk.push(highlight_line(&mut h, line));
+                if chunk.len() == CHUNK_LINES {
+                    if sender.send((start, std::mem::take(&mut chunk))).is_err() {
+                        return;
+                    }
+                    start = i + 1;
+                }
+            }
 
-    // タブをスペースに展開してから行分割する
-    let mut spans: Vec<Line> = Vec::with_capacity(500);
-    let processed = {
-        let s = text.to_string();
-        if tab_width == 0 {
-            s
-        } else {
-            s.replace('\t', &" ".repeat(tab_width))
+            if !chunk.is_empty() {
+                let _ = sender.send((start, chunk));
+            }
+        });
+
+        Some(BackgroundHighlight { receiver, cancel })
+    }
+
+    /// ハイライトし終わった行を全て受け取る。<br>
+    /// 全ての行を受け取り終わっていれば`true`も返す
+    pub fn receive(&self) -> (Vec<(usize, Vec<Line<'static>>)>, bool) {
+        let mut chunks = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(chunk) => chunks.push(chunk),
+                Err(TryRecvError::Empty) => return (chunks, false),
+                Err(TryRecvError::Disconnected) => return (chunks, true),
+            }
         }
-    };
+    }
+}
+
+impl Drop for BackgroundHighlight {
+    fn drop(&mut self) {
+        // 別のファイルを開いたらハイライトを中止する
+        self.cancel.store(true, Ordering::Release);
+    }
+}
+
+/// 名前からシンタックス定義を探す
+fn find_syntax(name: &str) -> Option<&'static SyntaxReference> {
+    Highlighter::get().syntaxes.find_syntax_by_name(name)
+}
+
+/// "rust"や"py"のような名前(大文字小文字は区別しない)か拡張子から、シンタックスの正式な名前を取得する
+pub fn syntax_name(token: &str) -> Option<&'static str> {
+    Highlighter::get()
+        .syntaxes
+        .find_syntax_by_token(token.trim())
+        .map(|syntax| syntax.name.as_str())
+}
+
+/// ファイル名と一行目からシンタックスを判定する。<br>
+/// `Makefile`や`.bashrc`のようなファイル名 → 拡張子 → `#!/usr/bin/env python`のような一行目 の順に調べる
+pub fn detect_syntax(file_name: &str, first_line: &str) -> Option<&'static str> {
+    let ps = &Highlighter::get().syntaxes;
+
+    ps.find_syntax_by_extension(file_name)
+        .or_else(|| {
+            Path::new(file_name)
+                .extension()
+                .and_then(|ext| ps.find_syntax_by_extension(&ext.to_string_lossy()))
+        })
+        .or_else(|| ps.find_syntax_by_first_line(first_line))
+        .map(|syntax| syntax.name.as_str())
+}
+
+/// 名前からテーマを探す。無ければ[DEFAULT_THEME]
+fn find_theme(theme: &str) -> &'static Theme {
+    let ts = &Highlighter::get().themes;
+    ts.themes.get(theme).unwrap_or(&ts.themes[DEFAULT_THEME])
+}
+
+/// タブをスペースに展開する。`tab_width`が0ならそのまま
+pub fn expand_tabs(text: String, tab_width: usize) -> String {
+    if tab_width == 0 {
+        text
+    } else {
+        text.replace('\t', &" ".repeat(tab_width))
+    }
+}
+
+/// 一行をハイライトする
+fn highlight_line(h: &mut HighlightLines, line: &str) -> Line<'static> {
+    let spans: Vec<Span> = h
+        .highlight_line(line, &Highlighter::get().syntaxes)
+        .unwrap()
+        .iter()
+        .map(|(style, text)| Span::styled(text.to_string(), into_color(style)))
+        .collect();
+
+    Line::from(spans)
+}
+
+/// 組み込みのテーマと、設定フォルダの`themes`にある`.tmTheme`ファイルを読み込む
+fn load_themes() -> ThemeSet {
+    let mut ts = ThemeSet::load_defaults();
+
+    if let Some(dir) = Config::dir().map(|dir| dir.join("themes")) {
+        if dir.is_dir() {
+            // 読み込めないファイルがあっても組み込みのテーマは使えるようにする
+            let _ = ts.add_from_folder(dir);
+        }
+    }
+
+    ts
+}
+
+/// 指定した名前のテーマが存在するか
+pub fn theme_exists(theme: &str) -> bool {
+    Highlighter::get().themes.themes.contains_key(theme)
+}
+
+/// `theme`の次のテーマ名を取得する。最後まで来たら先頭に戻る
+pub fn next_theme(theme: &str) -> String {
+    let ts = &Highlighter::get().themes;
+    // themesはBTreeMapなので名前順
+    let mut names = ts.themes.keys();
+
+    match names.position(|name| name == theme) {
+        Some(_) => names.next(),
+        None => None,
+    }
+    .or_else(|| ts.themes.keys().next())
+    .cloned()
+    .unwrap_or(DEFAULT_THEME.to_string())
+}
 
-    for line in processed.lines() {
-        let span: Vec<Span> = h
-            .highlight_line(line, &ps)
-            .unwrap()
-            .iter()
-            .map(|(style, text)| Span::styled(text.to_string(), into_color(style)))
-            .collect();
+/// [syntect::highlighting::Style]を[ratatui::style::Style]形式に変更する。<br>
+/// 文字色・背景色と太字・斜体・下線を反映する
+#[inline]
+fn into_color(style: &Style) -> ratatui::style::Style {
+    let mut modifier = Modifier::empty();
+    if style.font_style.contains(FontStyle::BOLD) {
+        modifier |= Modifier::BOLD;
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        modifier |= Modifier::ITALIC;
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        modifier |= Modifier::UNDERLINED;
+    }
 
-        spans.push(Line::from(span));
+    let mut converted = ratatui::style::Style::new()
+        .fg(rgb(style.foreground))
+        .add_modifier(modifier);
+    // 透明な背景色は表示部分の背景色をそのまま使う
+    if style.background.a > 0 {
+        converted = converted.bg(rgb(style.background));
     }
+    converted
+}
+
+/// テーマの文字色と背景色。表示部分全体に使う
+pub fn theme_style(theme: &str) -> ratatui::style::Style {
+    let settings = &find_theme(theme).settings;
+    let mut style = ratatui::style::Style::new().fg(Color::White);
 
-    Text::from(spans)
+    if let Some(foreground) = settings.foreground {
+        style = style.fg(rgb(foreground));
+    }
+    if let Some(background) = settings.background {
+        style = style.bg(rgb(background));
+    }
+    style
 }
 
-/// [syntect::highlighting::Style]を[tui::style::Style]形式に変更する
-///
-/// ### Note
-///     backgroundを変更すると文字の部分のみ反映されてしまうので実装していない。
-///     draw側で制御すれば良い感じになるかも？
+/// [syntect::highlighting::Color]を[Color]にする
 #[inline]
-const fn into_color(style: &Style) -> ratatui::style::Style {
-    ratatui::style::Style::new().fg(ratatui::style::Color::Rgb(
-        style.foreground.r,
-        style.foreground.g,
-        style.foreground.b,
-    ))
+const fn rgb(color: syntect::highlighting::Color) -> Color {
+    Color::Rgb(color.r, color.g, color.b)
+}
+
+#[test]
+fn detect_syntax_test() {
+    assert_eq!(detect_syntax("main.rs", ""), Some("Rust"));
+    assert_eq!(detect_syntax("Makefile", ""), Some("Makefile"));
+    assert_eq!(
+        detect_syntax(".bashrc", ""),
+        Some("Bourne Again Shell (bash)")
+    );
+    assert_eq!(
+        detect_syntax("script", "#!/usr/bin/env python"),
+        Some("Python")
+    );
+    assert_eq!(detect_syntax("script", "hello"), None);
+    assert_eq!(syntax_name("rust"), Some("Rust"));
+    assert_eq!(syntax_name("py"), Some("Python"));
+}
+
+#[test]
+fn load_syntaxes_test() {
+    let root = std::env::temp_dir().join(format!("tui-cat-syntax-{}", std::process::id()));
+    let dir = root.join("syntaxes");
+    let cache = root.join(SYNTAX_CACHE);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("test.sublime-syntax"),
+        "%YAML 1.2\n---\nname: TestLang\nfile_extensions: [testlang]\nscope: source.testlang\ncontexts:\n  main:\n    - match: '\\bfoo\\b'\n      scope: keyword.control.testlang\n",
+    )
+    .unwrap();
+
+    // 一回目は組み立ててキャッシュに保存し、二回目はキャッシュから読み込む
+    for _ in 0..2 {
+        let syntaxes = load_syntaxes(&dir, Some(&cache)).unwrap();
+        assert!(syntaxes.find_syntax_by_extension("testlang").is_some());
+        assert!(syntaxes.find_syntax_by_extension("rs").is_some());
+        assert!(cache.is_file());
+    }
+
+    fs::write(dir.join("broken.sublime-syntax"), "name: [").unwrap();
+    assert!(load_syntaxes(&dir, Some(&cache)).is_err());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn into_color_test() {
+    use syntect::highlighting::Color as SyntectColor;
+
+    let style = Style {
+        foreground: SyntectColor {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 0xff,
+        },
+        background: SyntectColor {
+            r: 4,
+            g: 5,
+            b: 6,
+            a: 0xff,
+        },
+        font_style: FontStyle::BOLD | FontStyle::UNDERLINE,
+    };
+    let converted = into_color(&style);
+    assert_eq!(converted.fg, Some(Color::Rgb(1, 2, 3)));
+    assert_eq!(converted.bg, Some(Color::Rgb(4, 5, 6)));
+    assert!(converted
+        .add_modifier
+        .contains(Modifier::BOLD | Modifier::UNDERLINED));
+    assert!(!converted.add_modifier.contains(Modifier::ITALIC));
+
+    let transparent = Style {
+        background: SyntectColor {
+            a: 0,
+            ..SyntectColor::BLACK
+        },
+        ..style
+    };
+    assert_eq!(into_color(&transparent).bg, None);
+}
+
+#[test]
+fn hylight_tab_test() {
+    // シンタックスが無くても、有っても一度だけ展開する
+    let plain = hylight("\ta", None, 2, DEFAULT_THEME);
+    assert_eq!(plain.lines[0].to_string(), "  a");
+    let rust = hylight("\tlet a = 1;", Some("Rust"), 2, DEFAULT_THEME);
+    assert_eq!(rust.lines[0].to_string(), "  let a = 1;");
+    let kept = hylight("\ta", None, 0, DEFAULT_THEME);
+    assert_eq!(kept.lines[0].to_string(), "\ta");
 }